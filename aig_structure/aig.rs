@@ -1,58 +1,160 @@
 use crate::aig_structure::signal::Signal;
 use crate::aig_structure::and_node::AndNode;
+use crate::aig_structure::latch::Latch;
 use std::collections::{HashMap, HashSet};
 
 /// Define an AIG struct.
 /// compute_table: used to check whether an AndNode has been calculated or not
 /// node_map: HashMap that contains all AndNodes and the id.
+/// inputs: vector that contains all input signals
+/// latches: vector that contains all latches (empty for combinational AIGs)
+/// outputs: vector that contains all output signals
+/// choices: structural choices. Maps a representative node to the alternative structures of its
+///          equivalence class (see choices.rs)
+/// representatives: the representative of every alternative, the reverse map of choices (kept up to date by
+///                  add_choice)
+/// debug_check: if set, `check` runs after every mutating operation (only in debug builds). The check
+///              always covers the whole AIG, so building an AIG with n nodes (e.g. reading a file) takes
+///              O(n²) time. Only meant for small AIGs in tests and while debugging.
+#[derive(Clone)]
 pub struct AIG {
     pub compute_table: HashMap<(Signal, Signal), Signal>,
     pub node_map: HashMap<usize, AndNode>,
+    pub inputs: Vec<Signal>,
+    pub latches: Vec<Latch>,
+    pub outputs: Vec<Signal>,
+    pub choices: HashMap<usize, Vec<Signal>>,
+    pub representatives: HashMap<usize, usize>,
+    pub debug_check: bool,
 }
+impl Default for AIG {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl AIG {
     pub fn new() -> Self {
         AIG {
             compute_table: HashMap::new(),
-            node_map: HashMap::new()
+            node_map: HashMap::new(),
+            inputs: Vec::new(),
+            latches: Vec::new(),
+            outputs: Vec::new(),
+            choices: HashMap::new(),
+            representatives: HashMap::new(),
+            debug_check: false,
         }
     }
 
-    pub fn create_and(&mut self, mut a: Signal, mut b: Signal, new_index: usize) -> Signal {
-        (a, b) = Self::check_swap(a, b);
+    /// add the input x_index and return its signal
+    pub fn add_input(&mut self, index: usize) -> Signal {
+        let signal = Signal::new(index, false);
+        self.inputs.push(signal);
+        self.after_mutation();
+        signal
+    }
+
+    /// add the latch x_index and return the signal of its output. The next state signal is set to
+    /// the constant 0 until `set_latch_next` is called, because it usually points to AndNodes that
+    /// do not exist yet.
+    pub fn add_latch(&mut self, index: usize, init: Option<bool>) -> Signal {
+        let signal = Signal::new(index, false);
+        self.latches.push(Latch { index, next: Signal::new(0, false), init });
+        self.after_mutation();
+        signal
+    }
+
+    /// set the next state signal of the latch at position `latch` in `latches`
+    pub fn set_latch_next(&mut self, latch: usize, next: Signal) {
+        self.latches[latch].next = next;
+        self.after_mutation();
+    }
+
+    /// add an output that is driven by the given signal
+    pub fn add_output(&mut self, signal: Signal) {
+        self.outputs.push(signal);
+        self.after_mutation();
+    }
 
+    pub fn create_and(&mut self, mut a: Signal, mut b: Signal, new_index: usize) -> Signal {
         (a, b) = Self::check_swap(a, b);                        // swap if a > b
 
-        if a.index == 0 && !a.inverted {                        // 0 and b = 0 remember: the signal (0, false) or (x_0, 0) represents the constant 0.  
+        if a.index() == 0 && !a.is_complemented() {                        // 0 and b = 0 remember: the signal (0, false) or (x_0, 0) represents the constant 0.  
             return Signal::new(0, false);
         }
 
-        if a.index == 0 && a.inverted{                          // 1 and b = b remember: the signal (0, true) or (x_0, 1) represents the constant 1.
+        if a.index() == 0 && a.is_complemented(){                          // 1 and b = b remember: the signal (0, true) or (x_0, 1) represents the constant 1.
             return b;
         }
 
-        if a.index == b.index && a.inverted != b.inverted{      // a and !a = 0
+        if a.index() == b.index() && a.is_complemented() != b.is_complemented(){      // a and !a = 0
             return Signal::new(0, false);
         }
 
-        if a.index == b.index && a.inverted == b.inverted{      // a and a = a
+        if a.index() == b.index() && a.is_complemented() == b.is_complemented(){      // a and a = a
             return a;
         }
         
         if let Some(&result) = self.compute_table.get(&(a, b)) { // check if x_i in compute table then return (x_i, 0)
-            return Signal::new(result.index, false);
+            return Signal::new(result.index(), false);
         } 
 
         // create a new AndNode an add it to the node_map
         let new_signal = Signal::new(new_index, false);
         self.compute_table.insert((a, b), new_signal);
-        self.node_map.insert(new_signal.index, AndNode{left_signal: a, right_signal: b});
+        self.node_map.insert(new_signal.index(), AndNode{left_signal: a, right_signal: b});
+        self.after_mutation();
+
+        new_signal
+    }
+
+    /// Replace both fanins of an existing AndNode and update the compute_table. The fanins are sorted like
+    /// in create_and. Returns false and does nothing if the edit is not allowed:
+    /// - `node_idx` has to be an AndNode
+    /// - the new fanins must not be trivial (a constant or the same node twice), create_and would not create a node
+    /// - no other AndNode may have the same fanins (structural hashing)
+    /// - the node must not be in the transitive fanin of a new fanin (cycle)
+    pub fn replace_fanins(&mut self, node_idx: usize, a: Signal, b: Signal) -> bool {
+        let (a, b) = Self::check_swap(a, b);
+        let Some(node) = self.node_map.get(&node_idx) else {
+            return false;
+        };
+        let old_fanins = (node.left_signal, node.right_signal);
+        if a.index() == 0 || a.index() == b.index() {
+            return false;
+        }
+        if self.compute_table.get(&(a, b)).is_some_and(|signal| signal.index() != node_idx) {
+            return false;
+        }
+        if self.in_transitive_fanin(node_idx, a.index()) || self.in_transitive_fanin(node_idx, b.index()) {
+            return false;
+        }
 
-        return new_signal;
+        self.compute_table.remove(&old_fanins);
+        self.compute_table.insert((a, b), Signal::new(node_idx, false));
+        self.node_map.insert(node_idx, AndNode { left_signal: a, right_signal: b });
+        self.after_mutation();
+        true
+    }
+
+    /// number of fanouts of every node: references from AndNodes, latch next states and outputs.
+    /// Nodes without fanouts are not in the map.
+    pub fn fanout_counts(&self) -> HashMap<usize, usize> {
+        let mut counts = HashMap::new();
+        for node in self.node_map.values() {
+            *counts.entry(node.left_signal.index()).or_insert(0) += 1;
+            *counts.entry(node.right_signal.index()).or_insert(0) += 1;
+        }
+        for signal in self.latches.iter().map(|l| l.next).chain(self.outputs.iter().copied()) {
+            *counts.entry(signal.index()).or_insert(0) += 1;
+        }
+        counts
     }
 
     /// swap if a > b
     fn check_swap(a: Signal, b: Signal) -> (Signal, Signal) {
-        if a.index > b.index { (b, a) } else { (a, b) }
+        if a.index() > b.index() { (b, a) } else { (a, b) }
     }
 
     /// Preparation for the topologically traverse part of the cut enumeration algorithm. 
@@ -63,20 +165,39 @@ impl AIG {
     /// 
     /// visited: contains the visited nodes in a hashset to make sure, we don't visit a node twice.
     /// order: the topoligally sorted order of nodes
+    ///
+    /// The node ids are visited in increasing order, so the order is the same on every run.
     pub fn topological_sort(&self) -> Vec<usize> {
         let mut visited = HashSet::new();
         let mut order = Vec::new();
-        
+
+        let mut node_ids: Vec<usize> = self.node_map.keys().copied().collect();
+        node_ids.sort_unstable();
+
         // the for-loop is necessary, to make sure that we visit every node. But why not start with the biggest node id? 
         // If we always begin with the biggest node we might not visit all nodes. Lecture 6 (aig_optimization ) Slide 11 
         // shows an example where it is necessary. If we start with node 9 we don't visit node 8 with this algorithm.
-        for &node_id in self.node_map.keys() {  
+        for node_id in node_ids {
             self.topological_visit(node_id, &mut visited, &mut order);
         }
 
         order
     }
 
+    /// Topological order of the transitive fanin of the given root nodes (the roots included).
+    /// Nodes that none of the roots depends on are not in the order. Like topological_sort, the order
+    /// contains the inputs and latches that are used as well.
+    pub fn topological_sort_from(&self, roots: &[usize]) -> Vec<usize> {
+        let mut visited = HashSet::new();
+        let mut order = Vec::new();
+
+        for &root in roots {
+            self.topological_visit(root, &mut visited, &mut order);
+        }
+
+        order
+    }
+
     /// Classic recursive search through the tree. 
     /// Example:            x1      x2      x3
     ///                      \      /       /
@@ -96,12 +217,47 @@ impl AIG {
         visited.insert(node_id);
 
         if let Some(node) = self.node_map.get(&node_id) {
-            self.topological_visit(node.left_signal.index, visited, order);
-            self.topological_visit(node.right_signal.index, visited, order);
+            self.topological_visit(node.left_signal.index(), visited, order);
+            self.topological_visit(node.right_signal.index(), visited, order);
+        }
+
+        // the choices of a node are processed before the node, because the cut enumeration
+        // merges their cuts into the cuts of the representative
+        if let Some(choices) = self.choices.get(&node_id) {
+            for choice in choices {
+                self.topological_visit(choice.index(), visited, order);
+            }
         }
 
         order.push(node_id);
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AIG;
 
+    #[test]
+    fn replace_fanins_keeps_the_aig_consistent() {
+        // x4 = x1 and x2, x5 = x4 and x3
+        let mut aig = AIG::new();
+        let x1 = aig.add_input(1);
+        let x2 = aig.add_input(2);
+        let x3 = aig.add_input(3);
+        let x4 = aig.create_and(x1, x2, 4);
+        let x5 = aig.create_and(x4, x3, 5);
+        aig.add_output(x5);
 
+        assert!(aig.replace_fanins(4, x3, !x1));
+        assert_eq!((aig.node_map[&4].left_signal, aig.node_map[&4].right_signal), (!x1, x3));
+        assert_eq!(aig.create_and(!x1, x3, 6), x4);
+        assert!(aig.check().is_ok());
+
+        // trivial, the structure of x4 again, a cycle through x5, not an AndNode
+        assert!(!aig.replace_fanins(5, x2, !x2));
+        assert!(!aig.replace_fanins(5, !x1, x3));
+        assert!(!aig.replace_fanins(4, x5, x1));
+        assert!(!aig.replace_fanins(2, x1, x3));
+        assert!(aig.check().is_ok());
+    }
 }
@@ -1,5 +1,6 @@
 use crate::aig_structure::signal::Signal;
 
+#[derive(Clone)]
 pub struct AndNode {
     pub left_signal: Signal,
     pub right_signal: Signal,
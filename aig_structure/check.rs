@@ -0,0 +1,335 @@
+use crate::aig_structure::aig::AIG;
+use crate::aig_structure::signal::Signal;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// A single problem that `AIG::check` found.
+/// ConstantIsAndNode: node 0 is reserved for the constant, but it is in the node_map
+/// InputIsAndNode: an input index is also used by an AndNode
+/// DuplicateInput: the same input index is registered twice
+/// LatchIsAndNode: a latch index is also used by an AndNode
+/// DuplicateLatch: the same latch index is registered twice or is also an input
+/// DanglingLatchNext: the next state of a latch points to an index that does not exist
+/// MissingFanin: an AndNode references an index that is neither the constant, an input nor an AndNode
+/// UnorderedFanins: the fanins of an AndNode are not sorted (left index <= right index), see create_and
+/// ComputeTableMissingNode: a compute_table entry points to an index that is not in the node_map
+/// ComputeTableMismatch: a compute_table entry points to an AndNode with different fanins
+/// NodeNotInComputeTable: an AndNode has no entry in the compute_table
+/// DuplicateNode: an AndNode has the same fanins as another AndNode (structural hashing would have returned
+///                the original instead)
+/// ChoiceMissingNode: a choice class references a representative or alternative that is not an AndNode
+/// Cycle: the AndNodes on a combinational cycle (in the order of the cycle), choices included
+/// DanglingOutput: an output points to an index that does not exist
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckIssue {
+    ConstantIsAndNode,
+    InputIsAndNode { index: usize },
+    DuplicateInput { index: usize },
+    LatchIsAndNode { index: usize },
+    DuplicateLatch { index: usize },
+    DanglingLatchNext { latch: usize, index: usize },
+    MissingFanin { node: usize, fanin: usize },
+    UnorderedFanins { node: usize },
+    ComputeTableMissingNode { fanins: (Signal, Signal), node: usize },
+    ComputeTableMismatch { node: usize, table_fanins: (Signal, Signal), node_fanins: (Signal, Signal) },
+    NodeNotInComputeTable { node: usize },
+    DuplicateNode { node: usize, original: usize },
+    ChoiceMissingNode { repr: usize, node: usize },
+    Cycle { nodes: Vec<usize> },
+    DanglingOutput { output: usize, index: usize },
+}
+
+/// The result of `AIG::check`. The AIG is consistent if there are no issues.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CheckReport {
+    pub issues: Vec<CheckIssue>,
+}
+
+impl CheckReport {
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl fmt::Display for CheckIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckIssue::ConstantIsAndNode => write!(f, "node 0 is reserved for the constant but is an AndNode"),
+            CheckIssue::InputIsAndNode { index } => write!(f, "input x{index} is also an AndNode"),
+            CheckIssue::DuplicateInput { index } => write!(f, "input x{index} is registered more than once"),
+            CheckIssue::LatchIsAndNode { index } => write!(f, "latch x{index} is also an AndNode"),
+            CheckIssue::DuplicateLatch { index } => write!(f, "latch x{index} is registered more than once or is also an input"),
+            CheckIssue::DanglingLatchNext { latch, index } => write!(f, "next state of latch {latch} points to missing node x{index}"),
+            CheckIssue::MissingFanin { node, fanin } => write!(f, "node x{node} references missing fanin x{fanin}"),
+            CheckIssue::UnorderedFanins { node } => write!(f, "node x{node} has unordered fanins (left index > right index)"),
+            CheckIssue::ComputeTableMissingNode { fanins, node } => write!(
+                f, "compute_table entry ({}, {}) points to missing node x{node}", fanins.0.literal(), fanins.1.literal()
+            ),
+            CheckIssue::ComputeTableMismatch { node, table_fanins, node_fanins } => write!(
+                f, "compute_table entry ({}, {}) points to node x{node} with fanins ({}, {})",
+                table_fanins.0.literal(), table_fanins.1.literal(), node_fanins.0.literal(), node_fanins.1.literal()
+            ),
+            CheckIssue::NodeNotInComputeTable { node } => write!(f, "node x{node} is missing in the compute_table"),
+            CheckIssue::DuplicateNode { node, original } => write!(f, "node x{node} has the same fanins as node x{original}"),
+            CheckIssue::ChoiceMissingNode { repr, node } => write!(f, "choice class of x{repr} references missing node x{node}"),
+            CheckIssue::Cycle { nodes } => {
+                let path: Vec<String> = nodes.iter().map(|n| format!("x{n}")).collect();
+                write!(f, "combinational cycle: {} -> x{}", path.join(" -> "), nodes[0])
+            }
+            CheckIssue::DanglingOutput { output, index } => write!(f, "output f{output} points to missing node x{index}"),
+        }
+    }
+}
+
+impl fmt::Display for CheckReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_ok() {
+            return writeln!(f, "AIG is consistent");
+        }
+        writeln!(f, "AIG check found {} issue(s):", self.issues.len())?;
+        for issue in &self.issues {
+            writeln!(f, "  - {issue}")?;
+        }
+        Ok(())
+    }
+}
+
+impl AIG {
+    /// Check the AIG for structural problems and return a report with all issues that were found.
+    /// The nodes are visited in increasing order, so the report is the same on every run.
+    pub fn check(&self) -> CheckReport {
+        let mut issues = Vec::new();
+
+        let mut node_ids: Vec<usize> = self.node_map.keys().copied().collect();
+        node_ids.sort_unstable();
+
+        // 1. constant, inputs and latches
+        if self.node_map.contains_key(&0) {
+            issues.push(CheckIssue::ConstantIsAndNode);
+        }
+        let mut input_ids = HashSet::new();
+        for input in &self.inputs {
+            if !input_ids.insert(input.index()) {
+                issues.push(CheckIssue::DuplicateInput { index: input.index() });
+            }
+            if self.node_map.contains_key(&input.index()) {
+                issues.push(CheckIssue::InputIsAndNode { index: input.index() });
+            }
+        }
+        let mut latch_ids = HashSet::new();
+        for latch in &self.latches {
+            if input_ids.contains(&latch.index) || !latch_ids.insert(latch.index) {
+                issues.push(CheckIssue::DuplicateLatch { index: latch.index });
+            }
+            if self.node_map.contains_key(&latch.index) {
+                issues.push(CheckIssue::LatchIsAndNode { index: latch.index });
+            }
+        }
+        let exists = |index: usize| {
+            index == 0 || input_ids.contains(&index) || latch_ids.contains(&index) || self.node_map.contains_key(&index)
+        };
+
+        // 2. fanins of every AndNode
+        for &node_id in &node_ids {
+            let node = &self.node_map[&node_id];
+            for fanin in [node.left_signal, node.right_signal] {
+                if !exists(fanin.index()) {
+                    issues.push(CheckIssue::MissingFanin { node: node_id, fanin: fanin.index() });
+                }
+            }
+            if node.left_signal.index() > node.right_signal.index() {
+                issues.push(CheckIssue::UnorderedFanins { node: node_id });
+            }
+        }
+
+        // 3. compute_table <-> node_map. Every entry has to point to a node with the same fanins
+        // and every node needs an entry. Two nodes with the same fanins cannot both have one.
+        let mut table_entries: Vec<(&(Signal, Signal), &Signal)> = self.compute_table.iter().collect();
+        table_entries.sort_unstable_by_key(|(fanins, _)| **fanins);
+        let mut hashed_nodes = HashSet::new();
+        for (&fanins, result) in table_entries {
+            match self.node_map.get(&result.index()) {
+                None => issues.push(CheckIssue::ComputeTableMissingNode { fanins, node: result.index() }),
+                Some(node) if (node.left_signal, node.right_signal) != fanins => {
+                    issues.push(CheckIssue::ComputeTableMismatch {
+                        node: result.index(),
+                        table_fanins: fanins,
+                        node_fanins: (node.left_signal, node.right_signal),
+                    });
+                }
+                Some(_) => {
+                    hashed_nodes.insert(result.index());
+                }
+            }
+        }
+        let mut structures: HashMap<(Signal, Signal), usize> = HashMap::new();
+        for &node_id in &node_ids {
+            let node = &self.node_map[&node_id];
+            let original = *structures.entry((node.left_signal, node.right_signal)).or_insert(node_id);
+            if original != node_id {
+                issues.push(CheckIssue::DuplicateNode { node: node_id, original });
+            } else if !hashed_nodes.contains(&node_id) {
+                issues.push(CheckIssue::NodeNotInComputeTable { node: node_id });
+            }
+        }
+
+        // 4. choices and cycles
+        let mut reprs: Vec<&usize> = self.choices.keys().collect();
+        reprs.sort_unstable();
+        for &repr in reprs {
+            let members = std::iter::once(repr).chain(self.choices[&repr].iter().map(|c| c.index()));
+            for node in members {
+                if !self.node_map.contains_key(&node) {
+                    issues.push(CheckIssue::ChoiceMissingNode { repr, node });
+                }
+            }
+        }
+        for cycle in self.find_cycles(&node_ids) {
+            issues.push(CheckIssue::Cycle { nodes: cycle });
+        }
+
+        // 5. latches and outputs
+        for (latch, l) in self.latches.iter().enumerate() {
+            if !exists(l.next.index()) {
+                issues.push(CheckIssue::DanglingLatchNext { latch, index: l.next.index() });
+            }
+        }
+        for (output, signal) in self.outputs.iter().enumerate() {
+            if !exists(signal.index()) {
+                issues.push(CheckIssue::DanglingOutput { output, index: signal.index() });
+            }
+        }
+
+        CheckReport { issues }
+    }
+
+    /// Depth first search over the fanins (and choices) with three colors. A fanin that is still on the stack (gray)
+    /// closes a cycle. It is iterative because the recursion in topological_visit would overflow the
+    /// stack exactly on the broken graphs we want to find here.
+    fn find_cycles(&self, node_ids: &[usize]) -> Vec<Vec<usize>> {
+        #[derive(Clone, Copy, PartialEq)]
+        enum Color { Gray, Black }
+
+        let mut color: HashMap<usize, Color> = HashMap::new();
+        let mut cycles = Vec::new();
+
+        for &start in node_ids {
+            if color.contains_key(&start) {
+                continue;
+            }
+            // stack of (node, number of fanins already visited)
+            let mut stack = vec![(start, 0usize)];
+            color.insert(start, Color::Gray);
+
+            while let Some(&mut (node_id, ref mut next)) = stack.last_mut() {
+                let node = &self.node_map[&node_id];
+                let choices = self.choices_of(node_id).iter().map(|c| c.index());
+                let fanins: Vec<usize> = [node.left_signal.index(), node.right_signal.index()].into_iter().chain(choices).collect();
+                if *next == fanins.len() {
+                    color.insert(node_id, Color::Black);
+                    stack.pop();
+                    continue;
+                }
+                let fanin = fanins[*next];
+                *next += 1;
+                if !self.node_map.contains_key(&fanin) {
+                    continue;
+                }
+                match color.get(&fanin) {
+                    None => {
+                        color.insert(fanin, Color::Gray);
+                        stack.push((fanin, 0));
+                    }
+                    Some(Color::Gray) => {
+                        let pos = stack.iter().position(|&(n, _)| n == fanin).unwrap();
+                        cycles.push(stack[pos..].iter().map(|&(n, _)| n).collect());
+                    }
+                    Some(Color::Black) => {}
+                }
+            }
+        }
+        cycles
+    }
+
+    /// Debug hook: runs `check` after every mutating operation if `debug_check` is set.
+    /// In release builds this does nothing. Every call checks the whole AIG, see `AIG::debug_check`.
+    pub(crate) fn after_mutation(&self) {
+        #[cfg(debug_assertions)]
+        if self.debug_check {
+            let report = self.check();
+            assert!(report.is_ok(), "AIG check failed after mutation:\n{report}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CheckIssue;
+    use crate::aig_structure::aig::AIG;
+    use crate::aig_structure::and_node::AndNode;
+    use crate::aig_structure::signal::Signal;
+
+    /// x3 = x1 and x2, x4 = x3 and !x1 with the output x4
+    fn example() -> AIG {
+        let mut aig = AIG::new();
+        let x1 = aig.add_input(1);
+        let x2 = aig.add_input(2);
+        let x3 = aig.create_and(x1, x2, 3);
+        let x4 = aig.create_and(x3, !x1, 4);
+        aig.add_output(x4);
+        aig
+    }
+
+    fn issues(aig: &AIG) -> Vec<CheckIssue> {
+        aig.check().issues
+    }
+
+    #[test]
+    fn consistent_aig_has_no_issues() {
+        assert!(example().check().is_ok());
+    }
+
+    #[test]
+    fn detects_a_cycle() {
+        // x3 = x1 and x4 closes the cycle x3 -> x4 -> x3
+        let mut aig = example();
+        aig.compute_table.clear();
+        aig.node_map.insert(3, AndNode { left_signal: Signal::new(1, false), right_signal: Signal::new(4, false) });
+        aig.compute_table.insert((Signal::new(1, false), Signal::new(4, false)), Signal::new(3, false));
+        aig.compute_table.insert((Signal::new(1, true), Signal::new(3, false)), Signal::new(4, false));
+        assert_eq!(issues(&aig), vec![CheckIssue::Cycle { nodes: vec![3, 4] }]);
+    }
+
+    #[test]
+    fn detects_a_missing_fanin() {
+        let mut aig = example();
+        let fanins = (Signal::new(1, false), Signal::new(7, false));
+        aig.node_map.insert(5, AndNode { left_signal: fanins.0, right_signal: fanins.1 });
+        aig.compute_table.insert(fanins, Signal::new(5, false));
+        assert_eq!(issues(&aig), vec![CheckIssue::MissingFanin { node: 5, fanin: 7 }]);
+    }
+
+    #[test]
+    fn detects_unordered_fanins() {
+        let mut aig = example();
+        let fanins = (Signal::new(2, false), Signal::new(1, true));
+        aig.node_map.insert(5, AndNode { left_signal: fanins.0, right_signal: fanins.1 });
+        aig.compute_table.insert(fanins, Signal::new(5, false));
+        assert_eq!(issues(&aig), vec![CheckIssue::UnorderedFanins { node: 5 }]);
+    }
+
+    #[test]
+    fn detects_a_duplicate_structure() {
+        // x5 = x1 and x2 like x3, but without structural hashing
+        let mut aig = example();
+        aig.node_map.insert(5, AndNode { left_signal: Signal::new(1, false), right_signal: Signal::new(2, false) });
+        assert_eq!(issues(&aig), vec![CheckIssue::DuplicateNode { node: 5, original: 3 }]);
+    }
+
+    #[test]
+    fn detects_a_dangling_output() {
+        let mut aig = example();
+        aig.outputs.push(Signal::new(9, true));
+        assert_eq!(issues(&aig), vec![CheckIssue::DanglingOutput { output: 1, index: 9 }]);
+    }
+}
@@ -0,0 +1,114 @@
+use crate::aig_structure::aig::AIG;
+use crate::aig_structure::signal::Signal;
+use std::collections::HashSet;
+
+/// Structural choices (like ABC's `dch`): functionally equivalent alternative structures of a node
+/// are kept together in an equivalence class. Every class has one representative, the alternatives
+/// are stored in `AIG::choices` as signals. A choice signal that is complemented means that the
+/// alternative node is equal to the inverted representative.
+///
+/// Example: repr = x1 and (x2 and x3), choice = (x1 and x2) and x3
+/// The cut enumeration merges the cuts of all alternatives into the cuts of the representative, so
+/// the representative also gets the cut {x1 and x2, x3} that its own structure does not have.
+///
+/// To keep the graph cycle-free, the representative must not be in the transitive fanin of an
+/// alternative (otherwise the cuts of the representative would depend on themselves).
+impl AIG {
+    /// Add `choice` to the equivalence class of the representative node `repr`.
+    /// Returns false and does nothing if the choice is not allowed:
+    /// - both nodes have to be AndNodes and different from each other
+    /// - a node can be in at most one class and a representative cannot be an alternative of another class
+    /// - `repr` must not be in the transitive fanin of `choice` (cycle)
+    pub fn add_choice(&mut self, repr: usize, choice: Signal) -> bool {
+        let alt = choice.index();
+        if alt == repr || !self.node_map.contains_key(&repr) || !self.node_map.contains_key(&alt) {
+            return false;
+        }
+        if self.choices.contains_key(&alt) || self.representative_of(alt).is_some() || self.representative_of(repr).is_some() {
+            return false;
+        }
+        if self.in_transitive_fanin(repr, alt) {
+            return false;
+        }
+
+        self.choices.entry(repr).or_default().push(choice);
+        self.representatives.insert(alt, repr);
+        self.after_mutation();
+        true
+    }
+
+    /// the alternatives of a representative (empty if the node has no choices)
+    pub fn choices_of(&self, repr: usize) -> &[Signal] {
+        self.choices.get(&repr).map(|c| c.as_slice()).unwrap_or(&[])
+    }
+
+    /// the representative of a node that is an alternative in some class
+    pub fn representative_of(&self, node: usize) -> Option<usize> {
+        self.representatives.get(&node).copied()
+    }
+
+    /// true if `target` is in the transitive fanin of `node` (the node itself included).
+    /// Choices count as fanins of their representative, because the cuts flow along them.
+    pub fn in_transitive_fanin(&self, target: usize, node: usize) -> bool {
+        let mut visited = HashSet::new();
+        let mut stack = vec![node];
+        while let Some(current) = stack.pop() {
+            if current == target {
+                return true;
+            }
+            if !visited.insert(current) {
+                continue;
+            }
+            if let Some(and_node) = self.node_map.get(&current) {
+                stack.push(and_node.left_signal.index());
+                stack.push(and_node.right_signal.index());
+            }
+            stack.extend(self.choices_of(current).iter().map(|c| c.index()));
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aig_structure::aig::AIG;
+    use crate::aig_structure::signal::Signal;
+
+    /// x4 = x2 and x3, x5 = x1 and x4, x6 = x1 and x2, x7 = x6 and x3: 5 and 7 are equivalent
+    fn example() -> AIG {
+        let mut aig = AIG::new();
+        let x1 = aig.add_input(1);
+        let x2 = aig.add_input(2);
+        let x3 = aig.add_input(3);
+        let x4 = aig.create_and(x2, x3, 4);
+        let x5 = aig.create_and(x1, x4, 5);
+        let x6 = aig.create_and(x1, x2, 6);
+        let x7 = aig.create_and(x6, x3, 7);
+        aig.add_output(x5);
+        aig.add_output(x7);
+        aig
+    }
+
+    #[test]
+    fn add_choice_keeps_the_reverse_map() {
+        let mut aig = example();
+        assert!(aig.add_choice(5, Signal::new(7, false)));
+        assert_eq!(aig.choices_of(5), &[Signal::new(7, false)]);
+        assert_eq!(aig.representative_of(7), Some(5));
+        assert_eq!(aig.representative_of(5), None);
+
+        // 7 is already in a class and 5 is a representative
+        assert!(!aig.add_choice(6, Signal::new(7, false)));
+        assert!(!aig.add_choice(6, Signal::new(5, false)));
+        assert!(aig.check().is_ok());
+    }
+
+    #[test]
+    fn add_choice_rejects_an_alternative_in_the_transitive_fanout() {
+        // 5 uses 4, so 5 as alternative of 4 would make the cuts of 4 depend on themselves
+        let mut aig = example();
+        assert!(aig.in_transitive_fanin(4, 5));
+        assert!(!aig.add_choice(4, Signal::new(5, false)));
+        assert!(aig.choices.is_empty() && aig.representatives.is_empty());
+    }
+}
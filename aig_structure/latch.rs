@@ -0,0 +1,12 @@
+use crate::aig_structure::signal::Signal;
+
+/// A latch (register) of a sequential AIG.
+/// index: index of the latch output. In AIGER order the latches come directly after the inputs: x_{I+1} ... x_{I+L}
+/// next: signal that is stored in the latch with the next clock cycle
+/// init: reset value of the latch. None means the latch is uninitialized (AIGER 1.9: init literal = latch literal)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Latch {
+    pub index: usize,
+    pub next: Signal,
+    pub init: Option<bool>,
+}
@@ -1,3 +1,7 @@
 pub mod aig;
 pub mod and_node;
+pub mod check;
+pub mod choices;
+pub mod latch;
+pub mod normalize;
 pub mod signal;
@@ -0,0 +1,167 @@
+use crate::aig_structure::aig::AIG;
+use crate::aig_structure::signal::Signal;
+use std::collections::HashMap;
+
+impl AIG {
+    /// Renumber the AIG into canonical AIGER order and return the new AIG together with the
+    /// old-to-new map. The new numbering is:
+    ///     0                       constant
+    ///     1, ..., I               inputs (in the order of `inputs`)
+    ///     I+1, ..., I+L           latches (in the order of `latches`)
+    ///     I+L+1, ..., I+L+A       AndNodes level by level (the level of a node is one more than the
+    ///                             largest level of its fanins, inputs and latches have level 0)
+    /// Because every AndNode is numbered after its fanins, lhs > rhs0 >= rhs1 holds for every
+    /// AndNode like the AIGER format requires. The node ids are dense again after edits.
+    /// The nodes of a level are sorted by the new literals of their fanins, so the numbering does not
+    /// depend on the old ids: two AIGs that only differ in the ids of their AndNodes get the same result.
+    ///
+    /// The AndNodes are rebuilt with create_and, so structurally equal nodes are merged and trivial
+    /// nodes (a and a, a and !a, ...) are simplified. That is why the map goes from an old index to a
+    /// new signal and not to a new index: an old node can end up as the complement of another node
+    /// or as a constant.
+    ///
+    /// The AIG has to be consistent (see `check`), otherwise this function panics.
+    pub fn normalize(&self) -> (AIG, HashMap<usize, Signal>) {
+        let mut aig = AIG::new();
+        aig.debug_check = self.debug_check;
+
+        let mut map: HashMap<usize, Signal> = HashMap::new();
+        map.insert(0, Signal::new(0, false));
+        let mut next_index = 1;
+
+        for input in &self.inputs {
+            map.insert(input.index(), aig.add_input(next_index));
+            next_index += 1;
+        }
+        for latch in &self.latches {
+            map.insert(latch.index, aig.add_latch(next_index, latch.init));
+            next_index += 1;
+        }
+
+        // old signal -> new signal, the complement of the old signal is kept
+        let translate = |map: &HashMap<usize, Signal>, signal: Signal| map[&signal.index()] ^ signal.is_complemented();
+
+        // topological_sort also contains the inputs and latches, we only need the AndNodes
+        let mut levels: HashMap<usize, usize> = HashMap::new();
+        let mut nodes_by_level: Vec<Vec<usize>> = Vec::new();
+        for node_id in self.topological_sort() {
+            let Some(node) = self.node_map.get(&node_id) else {
+                continue;
+            };
+            let fanin_level = |signal: Signal| levels.get(&signal.index()).copied().unwrap_or(0);
+            let level = 1 + fanin_level(node.left_signal).max(fanin_level(node.right_signal));
+            levels.insert(node_id, level);
+            if nodes_by_level.len() < level {
+                nodes_by_level.resize(level, Vec::new());
+            }
+            nodes_by_level[level - 1].push(node_id);
+        }
+
+        for nodes in nodes_by_level {
+            // all fanins are on lower levels and already have their new signal
+            let mut fanins: Vec<(usize, Signal, Signal)> = nodes.iter().map(|&node_id| {
+                let node = &self.node_map[&node_id];
+                let (left, right) = (translate(&map, node.left_signal), translate(&map, node.right_signal));
+                if left.literal() <= right.literal() { (node_id, left, right) } else { (node_id, right, left) }
+            }).collect();
+            fanins.sort_unstable_by_key(|&(node_id, left, right)| (left.literal(), right.literal(), node_id));
+
+            for (node_id, left, right) in fanins {
+                let new_signal = aig.create_and(left, right, next_index);
+
+                // only count up if create_and really created a new node
+                if aig.node_map.contains_key(&next_index) {
+                    next_index += 1;
+                }
+                map.insert(node_id, new_signal);
+            }
+        }
+
+        // choices: the phase of an alternative is relative to its representative, so the complements
+        // that the renumbering adds on both sides have to be taken into account. Classes that collapsed
+        // because create_and merged their nodes are rejected by add_choice.
+        let mut reprs: Vec<&usize> = self.choices.keys().collect();
+        reprs.sort_unstable();
+        for &repr in reprs {
+            let new_repr = map[&repr];
+            for &choice in &self.choices[&repr] {
+                let new_choice = map[&choice.index()];
+                let phase = new_repr.is_complemented() ^ choice.is_complemented() ^ new_choice.is_complemented();
+                aig.add_choice(new_repr.index(), Signal::new(new_choice.index(), phase));
+            }
+        }
+
+        for (k, latch) in self.latches.iter().enumerate() {
+            aig.set_latch_next(k, translate(&map, latch.next));
+        }
+        for &output in &self.outputs {
+            aig.add_output(translate(&map, output));
+        }
+
+        (aig, map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aig_structure::aig::AIG;
+    use crate::algorithms::cut_enumerator::CutEnumerator;
+    use crate::input_output::cut_writer::{write_cuts, CutFormat};
+
+    /// f = (x1 and x2) and !(x2 and x3), the AndNodes get the given ids
+    fn example(ids: [usize; 3]) -> AIG {
+        let mut aig = AIG::new();
+        let x1 = aig.add_input(1);
+        let x2 = aig.add_input(2);
+        let x3 = aig.add_input(3);
+        let (g, h) = if ids[0] < ids[1] {
+            (aig.create_and(x1, x2, ids[0]), aig.create_and(x2, x3, ids[1]))
+        } else {
+            let h = aig.create_and(x2, x3, ids[1]);
+            (aig.create_and(x1, x2, ids[0]), h)
+        };
+        let f = aig.create_and(!h, g, ids[2]);
+        aig.add_output(f);
+        aig
+    }
+
+    /// the cuts of all nodes like -e writes them
+    fn cut_output(aig: &AIG) -> String {
+        let mut cut_enumerator = CutEnumerator::new(aig);
+        cut_enumerator.compute_truth_tables = true;
+        cut_enumerator.enumerate_cuts(4, &aig.inputs);
+        let mut output = Vec::new();
+        write_cuts(&mut output, CutFormat::Text, &cut_enumerator.sorted_cuts(), None).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn isomorphic_aigs_get_the_same_numbering() {
+        let (first, first_map) = example([4, 5, 6]).normalize();
+        let (second, second_map) = example([20, 9, 15]).normalize();
+        assert!(first.check().is_ok() && second.check().is_ok());
+
+        let mut first_nodes: Vec<_> = first.node_map.iter().map(|(&id, node)| (id, node.left_signal, node.right_signal)).collect();
+        let mut second_nodes: Vec<_> = second.node_map.iter().map(|(&id, node)| (id, node.left_signal, node.right_signal)).collect();
+        first_nodes.sort_unstable();
+        second_nodes.sort_unstable();
+        assert_eq!(first_nodes, second_nodes);
+        assert_eq!(first.outputs, second.outputs);
+        assert_eq!(first_map[&4], second_map[&20]);
+        assert_eq!(first_map[&5], second_map[&9]);
+
+        assert_eq!(cut_output(&first), cut_output(&second));
+        assert_ne!(cut_output(&example([4, 5, 6])), cut_output(&example([20, 9, 15])));
+    }
+
+    #[test]
+    fn ids_are_dense_in_aiger_order() {
+        let (aig, _) = example([20, 9, 15]).normalize();
+        let mut ids: Vec<usize> = aig.node_map.keys().copied().collect();
+        ids.sort_unstable();
+        assert_eq!(ids, vec![4, 5, 6]);
+        for (&id, node) in &aig.node_map {
+            assert!(node.left_signal.index() <= node.right_signal.index() && node.right_signal.index() < id);
+        }
+    }
+}
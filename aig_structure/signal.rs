@@ -1,19 +1,74 @@
-#![allow(warnings)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)] // tells the compiler that it should use automatically standard functions for this struct
-
+use std::fmt;
+use std::ops::{BitXor, Not};
 
+/// A signal is an edge in the AIG: a node index together with a complement flag.
+/// Like in AIGER the signal is stored as a packed 32-bit literal: literal = index << 1 | complement.
+/// So the signal (x_3, inverted) is the literal 7 and the signal (x_3, not inverted) is the literal 6.
+/// This keeps a signal at 4 bytes instead of 16 bytes (usize + bool + padding).
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)] // tells the compiler that it should use automatically standard functions for this struct
 pub struct Signal {
-    pub index: usize,
-    pub inverted: bool
+    literal: u32,
 }
 
 impl Signal {
+    /// the largest node index that fits into a 32-bit literal (2^31 - 1)
+    pub const MAX_INDEX: usize = (u32::MAX >> 1) as usize;
+
+    /// Panics if the index is larger than MAX_INDEX, it would be truncated into a different node otherwise.
     pub fn new(index: usize, inverted: bool) -> Self {
-        Signal {index, inverted}
+        assert!(index <= Self::MAX_INDEX, "node index {index} does not fit into a 32-bit literal");
+        Signal { literal: ((index as u32) << 1) | inverted as u32 }
+    }
+
+    /// create a signal directly from an AIGER literal
+    pub fn from_literal(literal: u32) -> Self {
+        Signal { literal }
+    }
+
+    /// the AIGER literal of the signal: index << 1 | complement
+    pub fn literal(self) -> u32 {
+        self.literal
+    }
+
+    /// the index of the node the signal points to
+    pub fn index(self) -> usize {
+        (self.literal >> 1) as usize
+    }
+
+    /// true if the signal is inverted (the lowest bit of the literal is set)
+    pub fn is_complemented(self) -> bool {
+        self.literal & 1 == 1
     }
 
     pub fn invert(self) -> Self {
-        Signal::new(self.index, !self.inverted)
+        !self
     }
+}
 
-}
\ No newline at end of file
+/// !signal flips the complement bit.
+impl Not for Signal {
+    type Output = Signal;
+
+    fn not(self) -> Signal {
+        Signal { literal: self.literal ^ 1 }
+    }
+}
+
+/// signal ^ true flips the complement bit, signal ^ false leaves the signal unchanged.
+impl BitXor<bool> for Signal {
+    type Output = Signal;
+
+    fn bitxor(self, complement: bool) -> Signal {
+        Signal { literal: self.literal ^ complement as u32 }
+    }
+}
+
+/// Print the signal as (index, inverted) like the old struct did, that keeps debug output readable.
+impl fmt::Debug for Signal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Signal")
+            .field("index", &self.index())
+            .field("inverted", &self.is_complemented())
+            .finish()
+    }
+}
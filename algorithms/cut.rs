@@ -0,0 +1,144 @@
+use crate::algorithms::cut_cost::CutCost;
+use crate::algorithms::truth_table::TruthTable;
+use std::fmt;
+
+/// Maximum number of leaves a cut can have.
+pub const MAX_CUT_SIZE: usize = 16;
+
+/// A cut is stored as a small sorted array of leaves together with a 64-bit signature.
+/// leaves: the leaf indices in increasing order, only the first `size` entries are used (the rest is 0)
+/// size: number of leaves
+/// signature: bit (leaf % 64) is set for every leaf
+/// truth_table: optional function of the node over the leaves (variable i = i-th leaf), see CutEnumerator
+/// cost: depth, area flow, exact local area and leaf count, set by CutEnumerator (see cut_cost.rs)
+///
+/// The signature makes the dominance check cheap: if a cut c_1 is a subset of c_2, then every bit of the
+/// signature of c_1 is also set in the signature of c_2. So most pairs can be rejected without looking
+/// at the leaves at all. Because the leaves are sorted, the union of two cuts is a simple merge of two
+/// sorted lists and does not need a HashSet.
+#[derive(Clone, PartialEq)]
+pub struct Cut {
+    leaves: [u32; MAX_CUT_SIZE],
+    size: u8,
+    signature: u64,
+    pub truth_table: Option<TruthTable>,
+    pub cost: Option<CutCost>,
+}
+
+impl Cut {
+    /// the trivial cut {node}
+    pub fn trivial(node: usize) -> Self {
+        let mut cut = Cut { leaves: [0; MAX_CUT_SIZE], size: 1, signature: Self::leaf_bit(node), truth_table: None, cost: None };
+        cut.leaves[0] = node as u32;
+        cut
+    }
+
+    /// create a cut from leaves in any order. Duplicates are removed.
+    /// Panics if there are more than MAX_CUT_SIZE different leaves.
+    pub fn from_leaves(leaves: &[usize]) -> Self {
+        let mut sorted = leaves.to_vec();
+        sorted.sort_unstable();
+        sorted.dedup();
+        assert!(sorted.len() <= MAX_CUT_SIZE, "a cut can have at most {MAX_CUT_SIZE} leaves");
+
+        let mut cut = Cut { leaves: [0; MAX_CUT_SIZE], size: sorted.len() as u8, signature: 0, truth_table: None, cost: None };
+        for (i, &leaf) in sorted.iter().enumerate() {
+            cut.leaves[i] = leaf as u32;
+            cut.signature |= Self::leaf_bit(leaf);
+        }
+        cut
+    }
+
+    /// the bit of a leaf in the signature
+    fn leaf_bit(leaf: usize) -> u64 {
+        1u64 << (leaf % 64)
+    }
+
+    /// the leaves in increasing order
+    pub fn leaves(&self) -> &[u32] {
+        &self.leaves[..self.size as usize]
+    }
+
+    /// iterate over the leaves (in increasing order) as node indices
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.leaves().iter().map(|&leaf| leaf as usize)
+    }
+
+    pub fn len(&self) -> usize {
+        self.size as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    pub fn signature(&self) -> u64 {
+        self.signature
+    }
+
+    pub fn contains(&self, leaf: usize) -> bool {
+        self.signature & Self::leaf_bit(leaf) != 0 && self.leaves().binary_search(&(leaf as u32)).is_ok()
+    }
+
+    /// Union of two cuts as a merge of the two sorted leaf lists. Returns None as soon as the union
+    /// gets more than cut_size leaves. The union has no truth table and no cost data.
+    pub fn merge(&self, other: &Cut, cut_size: usize) -> Option<Cut> {
+        let cut_size = cut_size.min(MAX_CUT_SIZE);
+        // the signature tells us a lower bound of the union size for free
+        if (self.signature | other.signature).count_ones() as usize > cut_size {
+            return None;
+        }
+
+        let (a, b) = (self.leaves(), other.leaves());
+        let mut result = Cut {
+            leaves: [0; MAX_CUT_SIZE],
+            size: 0,
+            signature: self.signature | other.signature,
+            truth_table: None,
+            cost: None,
+        };
+        let (mut i, mut j, mut n) = (0, 0, 0);
+        while i < a.len() || j < b.len() {
+            let leaf = if j == b.len() || (i < a.len() && a[i] < b[j]) {
+                i += 1;
+                a[i - 1]
+            } else if i == a.len() || b[j] < a[i] {
+                j += 1;
+                b[j - 1]
+            } else {
+                // same leaf in both cuts
+                i += 1;
+                j += 1;
+                a[i - 1]
+            };
+            if n == cut_size {
+                return None;
+            }
+            result.leaves[n] = leaf;
+            n += 1;
+        }
+        result.size = n as u8;
+        Some(result)
+    }
+
+    /// true if self is a subset of other (self dominates other). Equal cuts dominate each other.
+    pub fn dominates(&self, other: &Cut) -> bool {
+        if self.size > other.size || self.signature & !other.signature != 0 {
+            return false;
+        }
+        // both lists are sorted: walk through other and find every leaf of self
+        let mut other_leaves = other.leaves().iter();
+        self.leaves().iter().all(|leaf| other_leaves.any(|l| l == leaf))
+    }
+}
+
+/// Print a cut like a set, e.g. {1, 2, 5}. If there is a truth table, it follows in hex: {1, 2}: 0x8
+impl fmt::Debug for Cut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.leaves()).finish()?;
+        if let Some(truth_table) = &self.truth_table {
+            write!(f, ": {truth_table}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,232 @@
+use crate::algorithms::cut::Cut;
+use crate::algorithms::cut_enumerator::{CutEnumerator, CutSortKey};
+use std::collections::HashMap;
+
+/// Cost data of a cut, used to rank the cuts of a node.
+/// depth: arrival depth of the cut, one level more than its deepest leaf (the depth of a leaf is the
+///        smallest depth of its own cuts, inputs have depth 0)
+/// area_flow: the AndNode itself plus the area flow of all leaves. The area flow of a node is split
+///            between its fanouts, so logic that is shared is not counted twice.
+/// exact_area: number of AndNodes that would be removed if the node was implemented with this cut:
+///             the node itself and all nodes between the node and the leaves that are not used
+///             anywhere else (the MFFC of the node bounded by the leaves)
+/// leaf_count: number of leaves
+#[derive(Debug, Clone, PartialEq)]
+pub struct CutCost {
+    pub depth: usize,
+    pub area_flow: f64,
+    pub exact_area: usize,
+    pub leaf_count: usize,
+}
+
+impl CutCost {
+    /// the metrics in the order in which they are compared for the given sort key
+    pub fn priority(&self, sort_key: CutSortKey) -> [f64; 4] {
+        let size = self.leaf_count as f64;
+        let depth = self.depth as f64;
+        let area_flow = self.area_flow;
+        let exact_area = self.exact_area as f64;
+        match sort_key {
+            CutSortKey::Size => [size, depth, area_flow, exact_area],
+            CutSortKey::Depth => [depth, size, area_flow, exact_area],
+            CutSortKey::AreaFlow => [area_flow, size, depth, exact_area],
+            CutSortKey::ExactArea => [exact_area, area_flow, size, depth],
+        }
+    }
+}
+
+/// The structure that implements a node with the leaves of one of its cuts.
+/// root: the node whose gate implements the root (the node itself or one of its alternatives)
+/// structure: the fanins that every AndNode of the cone uses, None if every node uses its own fanins
+///
+/// Without choices every cut is built from the fanins of the node, so the cone is the node and its
+/// fanins down to the leaves. With choices a cut can come from an alternative of the node or of a node
+/// inside the cone, then the own fanins can pass the leaves and reach nodes that the cut does not
+/// implement at all. Then every node of the cone uses its own fanins if they are bounded by the leaves,
+/// otherwise the first alternative that is, and the fanins point to the gates that were chosen.
+pub(crate) struct CutCone {
+    pub(crate) root: usize,
+    structure: Option<HashMap<usize, [usize; 2]>>,
+}
+
+impl CutCone {
+    /// the fanins of an AndNode of the cone
+    pub(crate) fn fanins(&self, enumerator: &CutEnumerator, node_idx: usize) -> [usize; 2] {
+        match &self.structure {
+            Some(structure) => structure[&node_idx],
+            None => {
+                let node = &enumerator.aig.node_map[&node_idx];
+                [node.left_signal.index(), node.right_signal.index()]
+            }
+        }
+    }
+}
+
+impl<'a> CutEnumerator<'a> {
+    /// The cone of a (non-trivial) cut of an AndNode, see CutCone.
+    pub(crate) fn cut_cone(&self, node_idx: usize, cut: &Cut) -> CutCone {
+        if self.aig.choices.is_empty() {
+            return CutCone { root: node_idx, structure: None };
+        }
+        let mut structure = HashMap::new();
+        let mut resolved = HashMap::new();
+        match self.resolve_gate(node_idx, cut, &mut structure, &mut resolved) {
+            Some(root) => CutCone { root, structure: Some(structure) },
+            // the cut was not built from this node (should not happen), keep the own fanins
+            None => CutCone { root: node_idx, structure: None },
+        }
+    }
+
+    /// The gate that implements a node with the leaves of the cut: a leaf or the constant itself, the node
+    /// if both of its fanins can be implemented, otherwise the first alternative that can. None if the
+    /// node cannot be implemented with the leaves (e.g. an input that is not a leaf).
+    /// structure: the fanins of every chosen gate, resolved: the result of every node that was already visited
+    fn resolve_gate(
+        &self,
+        node_idx: usize,
+        cut: &Cut,
+        structure: &mut HashMap<usize, [usize; 2]>,
+        resolved: &mut HashMap<usize, Option<usize>>,
+    ) -> Option<usize> {
+        if cut.contains(node_idx) || node_idx == 0 {
+            return Some(node_idx);
+        }
+        if let Some(&gate) = resolved.get(&node_idx) {
+            return gate;
+        }
+        let node = self.aig.node_map.get(&node_idx)?;
+
+        let mut gate = None;
+        let left = self.resolve_gate(node.left_signal.index(), cut, structure, resolved);
+        let right = left.and_then(|_| self.resolve_gate(node.right_signal.index(), cut, structure, resolved));
+        if let (Some(left), Some(right)) = (left, right) {
+            structure.insert(node_idx, [left, right]);
+            gate = Some(node_idx);
+        } else {
+            for choice in self.aig.choices_of(node_idx) {
+                gate = self.resolve_gate(choice.index(), cut, structure, resolved);
+                if gate.is_some() {
+                    break;
+                }
+            }
+        }
+        resolved.insert(node_idx, gate);
+        gate
+    }
+
+    /// compute the cost data of a cut of the given node. The leaves must have been processed already.
+    pub fn cut_cost(&self, node_idx: usize, cut: &Cut) -> CutCost {
+        CutCost {
+            depth: self.cut_depth(cut),
+            area_flow: self.cut_area_flow(cut),
+            exact_area: self.cut_exact_area(node_idx, cut),
+            leaf_count: cut.len(),
+        }
+    }
+
+    /// Exact local area: dereference the node and count all AndNodes whose reference count drops to 0.
+    /// The search stops at the leaves of the cut. For a cut of a choice, the gates of the structure that
+    /// the cut was built from are counted (see CutCone).
+    /// Instead of changing the reference counts and restoring them afterwards, we count how many of the
+    /// references of every node were removed. A node is freed once all of its fanouts are removed.
+    pub fn cut_exact_area(&self, node_idx: usize, cut: &Cut) -> usize {
+        let cone = self.cut_cone(node_idx, cut);
+        let mut removed_refs: HashMap<usize, usize> = HashMap::new();
+        let mut stack = vec![cone.root];
+        let mut area = 0;
+
+        while let Some(current) = stack.pop() {
+            area += 1;
+            for fanin in cone.fanins(self, current) {
+                if cut.contains(fanin) || !self.aig.node_map.contains_key(&fanin) {
+                    continue;
+                }
+                // an alternative has no fanouts in the graph, only the cone of this cut uses it
+                let removed = removed_refs.entry(fanin).or_insert(0);
+                *removed += 1;
+                if *removed == self.fanout_counts.get(&fanin).copied().unwrap_or(0).max(1) {
+                    stack.push(fanin);
+                }
+            }
+        }
+        area
+    }
+
+    /// all cuts of a node ranked by the given metric, best cut first
+    pub fn ranked_cuts(&self, node_idx: usize, sort_key: CutSortKey) -> Vec<Cut> {
+        let mut cuts = self.cuts.get(&node_idx).cloned().unwrap_or_default();
+        Self::sort_cuts(&mut cuts, sort_key);
+        cuts
+    }
+
+    /// all cuts of a node whose cost data fulfills the predicate, e.g. |cost| cost.depth <= 3
+    pub fn filter_cuts<F: Fn(&CutCost) -> bool>(&self, node_idx: usize, predicate: F) -> Vec<&Cut> {
+        self.cuts.get(&node_idx)
+            .map(|cuts| cuts.iter().filter(|cut| cut.cost.as_ref().is_some_and(&predicate)).collect())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aig_structure::aig::AIG;
+    use crate::aig_structure::signal::Signal;
+    use crate::algorithms::cut::Cut;
+    use crate::algorithms::cut_enumerator::CutEnumerator;
+
+    /// the example of choices.rs: 5 = x1 and (x2 and x3) with the alternative 7 = (x1 and x2) and x3
+    fn choice_example() -> AIG {
+        let mut aig = AIG::new();
+        let x1 = aig.add_input(1);
+        let x2 = aig.add_input(2);
+        let x3 = aig.add_input(3);
+        let n4 = aig.create_and(x2, x3, 4);
+        let n5 = aig.create_and(x1, n4, 5);
+        let n6 = aig.create_and(x1, x2, 6);
+        let n7 = aig.create_and(n6, x3, 7);
+        aig.add_output(n5);
+        assert!(aig.add_choice(5, Signal::new(n7.index(), false)));
+        aig
+    }
+
+    #[test]
+    fn choice_cut_is_measured_on_the_alternative() {
+        let aig = choice_example();
+        let mut cut_enumerator = CutEnumerator::new(&aig);
+        cut_enumerator.enumerate_cuts(3, &aig.inputs);
+
+        // {3, 6} only exists through the alternative 7: its cone is the gate 7, node 4 is not part of it
+        let choice_cut = Cut::from_leaves(&[3, 6]);
+        assert!(cut_enumerator.cuts[&5].iter().any(|cut| cut.leaves() == choice_cut.leaves()));
+        assert_eq!(cut_enumerator.cut_exact_area(5, &choice_cut), 1);
+        let volume = cut_enumerator.cut_volume(5, &choice_cut);
+        assert!(volume.internal.is_empty());
+        assert!(volume.fanout_free);
+
+        // {1, 2, 3} is a cut of the own structure: 5 and 4 are removed, 4 is internal
+        let own_cut = Cut::from_leaves(&[1, 2, 3]);
+        assert_eq!(cut_enumerator.cut_exact_area(5, &own_cut), 2);
+        assert_eq!(cut_enumerator.cut_volume(5, &own_cut).internal, vec![4]);
+
+        // {1, 4} of the own structure does not use the alternative
+        let fanin_cut = Cut::from_leaves(&[1, 4]);
+        assert_eq!(cut_enumerator.cut_exact_area(5, &fanin_cut), 1);
+        assert!(cut_enumerator.cut_volume(5, &fanin_cut).internal.is_empty());
+    }
+
+    #[test]
+    fn choice_cut_of_a_fanin_is_measured_on_the_alternative() {
+        // 8 = 5 and x1 inherits the cut {3, 6} of 5 as {1, 3, 6}: its cone is 8 and 7, not 8, 5 and 4
+        let mut aig = choice_example();
+        aig.outputs.clear();
+        let n8 = aig.create_and(Signal::new(5, false), Signal::new(1, false), 8);
+        aig.add_output(n8);
+        let mut cut_enumerator = CutEnumerator::new(&aig);
+        cut_enumerator.enumerate_cuts(3, &aig.inputs);
+
+        let cut = Cut::from_leaves(&[1, 3, 6]);
+        assert!(cut_enumerator.cuts[&8].iter().any(|c| c.leaves() == cut.leaves()));
+        assert_eq!(cut_enumerator.cut_exact_area(8, &cut), 2);
+        assert_eq!(cut_enumerator.cut_volume(8, &cut).internal, vec![7]);
+    }
+}
@@ -1,19 +1,115 @@
-#![allow(warnings)]
-
-use std::collections::{HashMap, HashSet};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+use std::sync::OnceLock;
+use std::time::Instant;
 use crate::aig_structure::aig::AIG;
 use crate::aig_structure::signal::Signal;
+use crate::algorithms::cut::{Cut, MAX_CUT_SIZE};
+use crate::algorithms::cut_cost::CutCost;
+use crate::algorithms::cut_limits::{estimated_memory, CutLimits, EnumerationStatus};
+use crate::algorithms::truth_table::TruthTable;
+
+/// Sort key for the priority cuts. If a cut limit is set, the cuts of a node are sorted by this key
+/// and only the best ones are kept.
+/// Size: fewest leaves first
+/// Depth: smallest depth first (depth of a cut = 1 + the largest depth of its leaves)
+/// AreaFlow: smallest area flow first (area of logic that is shared by several fanouts is split between them)
+/// ExactArea: smallest exact local area first (number of AndNodes that are only used by this cut, see cut_cost.rs)
+/// Ties are broken by the other metrics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CutSortKey {
+    #[default]
+    Size,
+    Depth,
+    AreaFlow,
+    ExactArea,
+}
+
+impl FromStr for CutSortKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "size" => Ok(CutSortKey::Size),
+            "depth" => Ok(CutSortKey::Depth),
+            "area-flow" | "area_flow" | "areaflow" => Ok(CutSortKey::AreaFlow),
+            "exact-area" | "exact_area" | "exactarea" => Ok(CutSortKey::ExactArea),
+            _ => Err(format!("unknown cut sort key '{s}' (expected size, depth, area-flow or exact-area)")),
+        }
+    }
+}
+
+/// Levels with fewer nodes are processed on the current thread in the parallel mode.
+const MIN_PARALLEL_LEVEL_SIZE: usize = 64;
+
+/// The result of CutEnumerator::compute_node for a single node.
+/// candidates: number of minimal cuts before the cut_limit was applied
+pub(crate) struct NodeCuts {
+    pub(crate) cuts: Vec<Cut>,
+    depth: usize,
+    area_flow: f64,
+    candidates: usize,
+}
+
+/// What the current run has used so far, compared with the CutLimits
+pub(crate) struct Usage {
+    start: Instant,
+    total_cuts: usize,
+    pub(crate) memory: usize,
+}
+
+impl Usage {
+    pub(crate) fn new() -> Self {
+        Usage { start: Instant::now(), total_cuts: 0, memory: 0 }
+    }
+}
+
+/// The usage of one level in the parallel mode, shared by all threads. Every thread adds its nodes and
+/// checks the limits after every node, the first limit that is hit stops all threads.
+struct SharedUsage {
+    start: Instant,
+    total_cuts: AtomicUsize,
+    memory: AtomicUsize,
+    stop: OnceLock<EnumerationStatus>,
+}
 
 /// CutEnumerator calculates all k-feasible cuts
 /// aig: graph for which the cuts are calculated
 /// cuts: HashMap containing all cuts
 /// topo_order: vector cotaining all nodes in a topological order
 /// num_inputs: number of input signals
+/// cut_limit: if set, only the best cut_limit cuts of every node are kept (priority cuts). The trivial cut is one of them,
+///            so the limit has to be at least 1 (the enumeration panics for 0).
+/// sort_key: decides which cuts are the best ones if a cut_limit is set
+/// depth: depth of every processed node (smallest depth of its cuts, inputs have depth 0)
+/// area_flow: area flow of every processed node (smallest area flow of its cuts divided by its fanouts)
+/// fanout_counts: number of fanouts of every node, needed for the area flow
+/// compute_truth_tables: if set, every cut gets the truth table of its node over the leaves of the cut
+/// threads: number of threads for enumerate_cuts. With more than one thread, the nodes of each topological
+///          level are processed in parallel (see process_levels_parallel)
+/// skipped_nodes: number of AndNodes of the last run that were not processed, because they are not in the
+///                transitive fanin of the requested outputs or target node
+/// limits: optional limits for time, number of cuts and memory (see cut_limits.rs)
+/// status: whether the last run processed all nodes or which limit stopped it
+/// roots: the roots of the last run, None if enumerate_cuts chose them (needed by update_cuts)
 pub struct CutEnumerator<'a> {
     pub aig: &'a AIG,
-    pub cuts: HashMap<usize, Vec<HashSet<usize>>>,
+    pub cuts: HashMap<usize, Vec<Cut>>,
     pub topo_order: Vec<usize>,
     pub num_inputs: usize,
+    pub cut_limit: Option<usize>,
+    pub sort_key: CutSortKey,
+    pub depth: HashMap<usize, usize>,
+    pub area_flow: HashMap<usize, f64>,
+    pub fanout_counts: HashMap<usize, usize>,
+    pub compute_truth_tables: bool,
+    pub threads: usize,
+    pub skipped_nodes: usize,
+    pub limits: CutLimits,
+    pub status: EnumerationStatus,
+    roots: Option<Vec<usize>>,
 }
 
 impl<'a> CutEnumerator<'a> {
@@ -23,6 +119,17 @@ impl<'a> CutEnumerator<'a> {
             cuts: HashMap::new(),
             topo_order: Vec::new(),
             num_inputs: 0,
+            cut_limit: None,
+            sort_key: CutSortKey::default(),
+            depth: HashMap::new(),
+            area_flow: HashMap::new(),
+            fanout_counts: HashMap::new(),
+            compute_truth_tables: false,
+            threads: 1,
+            skipped_nodes: 0,
+            limits: CutLimits::default(),
+            status: EnumerationStatus::Complete,
+            roots: None,
         }
     }
 
@@ -30,140 +137,755 @@ impl<'a> CutEnumerator<'a> {
     /// cut_size: maximum number of leaves for a cut.
     /// inputs: list of all input signals
     /// target_node: calculate all k-feasible cuts for this node
-    // TODO: cut_limit: maximum number of cuts for a node.
-    pub fn calculate_cuts_single_node(&mut self, cut_size: usize, inputs: &[Signal], target_node: usize) -> Vec<HashSet<usize>> {
-        self.cuts.clear();
-        self.topo_order = self.aig.topological_sort();
+    /// Only the transitive fanin of the target node is processed.
+    /// Returns None if the target node is neither an input, a latch nor an AndNode of the AIG.
+    pub fn calculate_cuts_single_node(&mut self, cut_size: usize, inputs: &[Signal], target_node: usize) -> Option<Vec<Cut>> {
+        self.reset(cut_size);
 
-        // check if the target node is part of the AIG. If not return an empty vector.
-        let is_in_input = inputs.iter().any(|sig| sig.index == target_node);
-        let is_in_topo = self.topo_order.contains(&target_node);
-        if !is_in_input && !is_in_topo {
-            eprintln!("Warning: target_node {target_node} not found in AIG or inputs.");
-            return vec![];
+        // check if the target node is part of the AIG
+        let is_in_input = inputs.iter().any(|sig| sig.index() == target_node);
+        let is_latch = self.aig.latches.iter().any(|latch| latch.index == target_node);
+        let is_and_node = self.aig.node_map.contains_key(&target_node);
+        if !is_in_input && !is_latch && !is_and_node {
+            return None;
         }
 
-        // get all relevant nodes to compute the cuts of the target_node
-        let mut relevant_nodes = Vec::new();
-        if let Some(pos) = self.topo_order.iter().position(|&id| id == target_node) {
-            relevant_nodes = self.topo_order[..=pos].to_vec(); // inclusive slice
-        }
-
-        // If there are no AndNodes in the graph, then topo order would be empty.
-        // But what if we have just a constant as an output? Then we only need to compute
-        // cuts for the target node itself.
-        if relevant_nodes.is_empty() {
-            relevant_nodes = vec![target_node];
-        }
+        // get all relevant nodes to compute the cuts of the target_node: its transitive fanin.
+        // For an input this is only the input itself.
+        self.topo_order = self.aig.topological_sort_from(&[target_node]);
+        self.roots = Some(vec![target_node]);
 
         // 1. topologically traverse
-        for &node_idx in relevant_nodes.iter() {
-            if let Some(node) = self.aig.node_map.get(&node_idx) {
-                // it is an AndNode
-                let new_cuts = self.compute_node_cuts(node_idx, cut_size);
-                let mut minimal = Self::filter_minimal_cuts(&new_cuts);
-                
-                // the node is always part of its own cut and is added at the end. 
-                // This is necessary for the cut_limit implementation.
-                let mut set = HashSet::new();
-                set.insert(node_idx);
-                minimal.push(set);
-
-                self.cuts.insert(node_idx, minimal);
-            } else {
-                // it is not an AndNode -> Input
-                let mut set = HashSet::new();
-                set.insert(node_idx);
-                self.cuts.insert(node_idx, vec![set]);
-            }
-        }
+        self.process_topo_order(cut_size);
 
-        self.cuts.get(&target_node).cloned().unwrap_or_default()
+        Some(self.cuts.get(&target_node).cloned().unwrap_or_default())
 
     }
 
-    /// Calculates all minimal cuts for all nodes
+    /// Calculates all minimal cuts for all nodes that are in the transitive fanin of an output or
+    /// a latch. Nodes that no output uses are skipped (see skipped_nodes). If the AIG has no outputs
+    /// and no latches (e.g. an AIG that is built by hand), all nodes are processed.
     /// cut_size: maximum number of leaves for a cut.
     /// inputs: list of all input signals
-    // TODO: cut_limit: maximum number of cuts for a node.
     pub fn enumerate_cuts(&mut self, cut_size: usize, inputs: &[Signal]) {
-        self.cuts.clear();
-        self.topo_order = self.aig.topological_sort();
+        let roots = self.default_roots();
+        self.enumerate_cuts_for(cut_size, inputs, &roots);
+        self.roots = None;
+    }
+
+    /// the roots of enumerate_cuts: all outputs and latch next states, or all nodes if there are none.
+    /// The nodes are sorted, so the topological order (and a partial result under limits) is the same on every run.
+    pub(crate) fn default_roots(&self) -> Vec<usize> {
+        if self.aig.outputs.is_empty() && self.aig.latches.is_empty() {
+            let mut node_ids: Vec<usize> = self.aig.node_map.keys().copied().collect();
+            node_ids.sort_unstable();
+            return node_ids;
+        }
+        self.aig.outputs.iter()
+            .chain(self.aig.latches.iter().map(|latch| &latch.next))
+            .map(|signal| signal.index())
+            .collect()
+    }
+
+    /// Calculates all minimal cuts for all nodes in the transitive fanin of the given root nodes
+    /// cut_size: maximum number of leaves for a cut.
+    /// inputs: list of all input signals
+    /// roots: nodes whose transitive fanin is processed (the roots included)
+    pub fn enumerate_cuts_for(&mut self, cut_size: usize, inputs: &[Signal], roots: &[usize]) {
+        self.reset(cut_size);
+
+        // the constant is not a node, it never gets cuts
+        let roots: Vec<usize> = roots.iter().copied().filter(|&root| root != 0).collect();
+        self.topo_order = self.aig.topological_sort_from(&roots);
+        self.roots = Some(roots);
 
         // If there are no AndNodes in the graph, then topo order would be empty.
         // But what if we have just a constant as an output? Therefore we have to fill
         // the topo order with the input ids.
         if self.topo_order.is_empty() {
-            self.topo_order = inputs.iter().map(|sig| sig.index).collect();
+            self.topo_order = inputs.iter().map(|sig| sig.index()).collect();
         }
 
         // 1. topologically traverse
-        for &node_idx in self.topo_order.iter() {
-            if let Some(node) = self.aig.node_map.get(&node_idx) {
-                // it is an AndNode
-                let new_cuts = self.compute_node_cuts(node_idx, cut_size);
-                let mut minimal = Self::filter_minimal_cuts(&new_cuts);
-                
-                // the node is always part of its own cut and is added at the end. 
-                // This is necessary for the cut_limit implementation.
-                let mut set = HashSet::new();
-                set.insert(node_idx);
-                minimal.push(set);
-
-                self.cuts.insert(node_idx, minimal);
-            } else {
-                // it is not an AndNode -> Input
-                let mut set = HashSet::new();
-                set.insert(node_idx);
-                self.cuts.insert(node_idx, vec![set]);
-            }
-        }
-    }
-
-    /// compute all non-filtered cuts for a given node
-    fn compute_node_cuts(&self, node_idx: usize, cut_size: usize) -> Vec<HashSet<usize>> {
+        self.process_topo_order(cut_size);
+    }
+
+    /// Use the results of this enumerator for another AIG, usually an edited copy of the old one.
+    /// Nothing is recomputed, call update_cuts with the nodes that were changed afterwards.
+    pub fn rebind<'b>(self, aig: &'b AIG) -> CutEnumerator<'b> {
+        CutEnumerator {
+            aig,
+            cuts: self.cuts,
+            topo_order: self.topo_order,
+            num_inputs: self.num_inputs,
+            cut_limit: self.cut_limit,
+            sort_key: self.sort_key,
+            depth: self.depth,
+            area_flow: self.area_flow,
+            fanout_counts: self.fanout_counts,
+            compute_truth_tables: self.compute_truth_tables,
+            threads: self.threads,
+            skipped_nodes: self.skipped_nodes,
+            limits: self.limits,
+            status: self.status,
+            roots: self.roots,
+        }
+    }
+
+    /// Incremental update after the AIG was edited: only the cuts of the changed nodes and of the nodes that
+    /// depend on them are computed again, all other cuts are kept. The same roots as in the last run are used
+    /// (enumerate_cuts chooses them again, so new outputs are taken into account).
+    /// cut_size: maximum number of leaves for a cut, should be the same as in the last run
+    /// changed: AndNodes that were added or whose fanins were replaced
+    /// Returns the number of nodes whose cuts were computed again.
+    ///
+    /// Besides the changed nodes, every node whose number of fanouts changed is computed again (its area
+    /// flow depends on it). A node is only computed again because of its fanins (or choices) if the cuts of
+    /// a fanin really changed or if a leaf of these cuts has a new depth or area flow, so the update stops
+    /// where the edit has no effect. The exact area of a cut also depends on the fanouts of the nodes inside
+    /// the cut, so a node is computed again as well if the cut of a fanin contains a node whose number of
+    /// fanouts changed (with choices: if such a node is anywhere in its transitive fanin).
+    /// The cuts of nodes that are no longer in the transitive fanin of the roots (e.g. nodes that were
+    /// replaced) are removed. The limits are checked for every node that is computed again, if one is hit
+    /// the update stops and the cuts are a partial result (see status).
+    pub fn update_cuts(&mut self, cut_size: usize, changed: &[usize]) -> usize {
+        assert!(cut_size <= MAX_CUT_SIZE, "cut_size {cut_size} is larger than the maximum cut size {MAX_CUT_SIZE}");
+        assert!(self.cut_limit != Some(0), "cut_limit has to be at least 1 (the trivial cut is always kept)");
+        self.status = EnumerationStatus::Complete;
+
+        let fanout_counts = self.aig.fanout_counts();
+        let mut fanouts_changed: HashSet<usize> = HashSet::new();
+        for node in fanout_counts.keys().chain(self.fanout_counts.keys()) {
+            if fanout_counts.get(node) != self.fanout_counts.get(node) {
+                fanouts_changed.insert(*node);
+            }
+        }
+        self.fanout_counts = fanout_counts;
+        let dirty: HashSet<usize> = changed.iter().copied().chain(fanouts_changed.iter().copied()).collect();
+
+        let roots = match &self.roots {
+            Some(roots) => roots.iter().copied().filter(|&root| root != 0).collect(),
+            None => self.default_roots(),
+        };
+        self.topo_order = self.aig.topological_sort_from(&roots);
+        if self.topo_order.is_empty() {
+            self.topo_order = self.aig.inputs.iter().map(|sig| sig.index()).collect();
+        }
+
+        // remove the results of nodes that are not processed anymore
+        let in_order: HashSet<usize> = self.topo_order.iter().copied().collect();
+        self.cuts.retain(|node, _| in_order.contains(node));
+        self.depth.retain(|node, _| in_order.contains(node));
+        self.area_flow.retain(|node, _| in_order.contains(node));
+
+        // modified: nodes whose cuts are different now
+        // metrics_changed: nodes whose depth or area flow is different now
+        // reaches_changed: nodes with a cut that contains a node with a changed number of fanouts
+        let mut modified: HashSet<usize> = HashSet::new();
+        let mut metrics_changed: HashSet<usize> = HashSet::new();
+        let mut reaches_changed: HashSet<usize> = HashSet::new();
+        let mut recomputed = 0;
+        let mut usage = Usage::new();
+        for node_idx in self.topo_order.clone() {
+            let fanins: Vec<usize> = match self.aig.node_map.get(&node_idx) {
+                Some(node) => [node.left_signal, node.right_signal].iter()
+                    .chain(self.aig.choices_of(node_idx))
+                    .map(|signal| signal.index())
+                    .collect(),
+                None => Vec::new(),
+            };
+            // every new cut is built from the cuts of the fanins, its leaves are leaves of these cuts and its
+            // cone is inside the cones of these cuts and the fanins themselves
+            let fanin_leaf_changed = fanins.iter()
+                .filter_map(|fanin| self.cuts.get(fanin))
+                .flatten()
+                .any(|cut| cut.iter().any(|leaf| metrics_changed.contains(&leaf)));
+            let fanin_reaches_changed = fanins.iter()
+                .any(|fanin| fanouts_changed.contains(fanin) || reaches_changed.contains(fanin));
+
+            let is_new = !self.cuts.contains_key(&node_idx);
+            let recompute = is_new || dirty.contains(&node_idx) || fanin_reaches_changed || fanin_leaf_changed
+                || fanins.iter().any(|fanin| modified.contains(fanin));
+            if !recompute {
+                continue;
+            }
+
+            let result = self.compute_node(node_idx, cut_size);
+            if let Err(status) = self.check_limits(node_idx, &result, &mut usage) {
+                self.status = status;
+                break;
+            }
+            if is_new || self.cuts[&node_idx] != result.cuts {
+                modified.insert(node_idx);
+            }
+            if is_new || self.depth[&node_idx] != result.depth || self.area_flow[&node_idx] != result.area_flow {
+                metrics_changed.insert(node_idx);
+            }
+            self.store_node(node_idx, result);
+            recomputed += 1;
+            // with choices a cone can switch between the alternatives, so it is not bounded by the cones of
+            // the fanin cuts anymore
+            if fanin_reaches_changed && (!self.aig.choices.is_empty() || self.cuts_contain(node_idx, &fanouts_changed)) {
+                reaches_changed.insert(node_idx);
+            }
+        }
+
+        self.count_skipped_nodes();
+        recomputed
+    }
+
+    /// true if the cone of one of the stored cuts of a node contains one of the given nodes (the node
+    /// itself and the leaves are not part of the cone)
+    fn cuts_contain(&self, node_idx: usize, nodes: &HashSet<usize>) -> bool {
+        let Some(cuts) = self.cuts.get(&node_idx) else {
+            return false;
+        };
+        cuts.iter().filter(|cut| !cut.contains(node_idx)).any(|cut| {
+            let cone = self.cut_cone(node_idx, cut);
+            let mut visited: HashSet<usize> = HashSet::from([cone.root]);
+            let mut stack = vec![cone.root];
+            while let Some(current) = stack.pop() {
+                for fanin in cone.fanins(self, current) {
+                    if cut.contains(fanin) || !self.aig.node_map.contains_key(&fanin) {
+                        continue;
+                    }
+                    if nodes.contains(&fanin) {
+                        return true;
+                    }
+                    if visited.insert(fanin) {
+                        stack.push(fanin);
+                    }
+                }
+            }
+            false
+        })
+    }
+
+    /// Process all nodes of topo_order and count the AndNodes that were skipped. The run stops as soon
+    /// as one of the limits is hit, the status tells which one.
+    fn process_topo_order(&mut self, cut_size: usize) {
+        let mut usage = Usage::new();
+        self.status = if self.threads > 1 {
+            self.process_levels_parallel(cut_size, &mut usage)
+        } else {
+            self.process_sequential(cut_size, &mut usage)
+        };
+        self.count_skipped_nodes();
+    }
+
+    /// process the nodes of topo_order one after another
+    fn process_sequential(&mut self, cut_size: usize, usage: &mut Usage) -> EnumerationStatus {
+        for node_idx in self.topo_order.clone() {
+            let result = self.compute_node(node_idx, cut_size);
+            if let Err(status) = self.check_limits(node_idx, &result, usage) {
+                return status;
+            }
+            self.store_node(node_idx, result);
+        }
+        EnumerationStatus::Complete
+    }
+
+    /// Check if the result of a node still fits into the limits and add it to the usage. The time is
+    /// checked as well, a node that was computed after the time limit is not stored.
+    pub(crate) fn check_limits(&self, node_idx: usize, result: &NodeCuts, usage: &mut Usage) -> Result<(), EnumerationStatus> {
+        let limits = &self.limits;
+        if limits.max_cuts_per_node.is_some_and(|max| result.candidates > max) {
+            return Err(EnumerationStatus::NodeCutLimit { node: node_idx });
+        }
+        usage.total_cuts += result.cuts.len();
+        if limits.max_total_cuts.is_some_and(|max| usage.total_cuts > max) {
+            return Err(EnumerationStatus::TotalCutLimit);
+        }
+        if let Some(max) = limits.max_memory {
+            usage.memory += estimated_memory(&result.cuts);
+            if usage.memory > max {
+                return Err(EnumerationStatus::MemoryLimit);
+            }
+        }
+        if limits.max_time.is_some_and(|max| usage.start.elapsed() > max) {
+            return Err(EnumerationStatus::TimeLimit);
+        }
+        Ok(())
+    }
+
+    /// check_limits for the threads of the parallel mode: the usage of all threads together is checked
+    fn check_shared_limits(&self, node_idx: usize, result: &NodeCuts, usage: &SharedUsage) -> Result<(), EnumerationStatus> {
+        let limits = &self.limits;
+        if limits.max_cuts_per_node.is_some_and(|max| result.candidates > max) {
+            return Err(EnumerationStatus::NodeCutLimit { node: node_idx });
+        }
+        let total_cuts = usage.total_cuts.fetch_add(result.cuts.len(), AtomicOrdering::Relaxed) + result.cuts.len();
+        if limits.max_total_cuts.is_some_and(|max| total_cuts > max) {
+            return Err(EnumerationStatus::TotalCutLimit);
+        }
+        if let Some(max) = limits.max_memory {
+            let memory = estimated_memory(&result.cuts);
+            if usage.memory.fetch_add(memory, AtomicOrdering::Relaxed) + memory > max {
+                return Err(EnumerationStatus::MemoryLimit);
+            }
+        }
+        if limits.max_time.is_some_and(|max| usage.start.elapsed() > max) {
+            return Err(EnumerationStatus::TimeLimit);
+        }
+        Ok(())
+    }
+
+    /// the AndNodes that are not in topo_order
+    pub(crate) fn count_skipped_nodes(&mut self) {
+        let processed = self.topo_order.iter().filter(|id| self.aig.node_map.contains_key(id)).count();
+        self.skipped_nodes = self.aig.node_map.len() - processed;
+    }
+
+    /// Parallel mode: the nodes of topo_order are grouped by their level (inputs have level 0, an AndNode
+    /// has one level more than its highest fanin or choice). The cuts of a node only depend on nodes of lower
+    /// levels, so all nodes of one level can be computed at the same time. Every level is split into one
+    /// chunk per thread. The threads only read the results of the lower levels, the results are stored
+    /// after all threads are finished and in the order of topo_order, so the result is the same as in
+    /// the sequential mode.
+    /// The threads check the limits after every node against the usage of all threads together (see
+    /// SharedUsage), so a wide level cannot run far past a limit. The limits are checked again while the
+    /// results are stored, so the stored cuts never exceed a limit. Which nodes of the last level are
+    /// stored can differ from the sequential mode.
+    fn process_levels_parallel(&mut self, cut_size: usize, usage: &mut Usage) -> EnumerationStatus {
+        let mut level_of: HashMap<usize, usize> = HashMap::new();
+        let mut levels: Vec<Vec<usize>> = Vec::new();
+        for &node_idx in &self.topo_order {
+            let level = match self.aig.node_map.get(&node_idx) {
+                None => 0,
+                Some(node) => {
+                    let fanins = [node.left_signal, node.right_signal].into_iter()
+                        .chain(self.aig.choices_of(node_idx).iter().copied());
+                    1 + fanins.map(|signal| level_of.get(&signal.index()).copied().unwrap_or(0)).max().unwrap_or(0)
+                }
+            };
+            level_of.insert(node_idx, level);
+            if levels.len() <= level {
+                levels.resize(level + 1, Vec::new());
+            }
+            levels[level].push(node_idx);
+        }
+
+        for level in levels {
+            // starting threads costs more than computing a few nodes
+            if level.len() < MIN_PARALLEL_LEVEL_SIZE {
+                for node_idx in level {
+                    let result = self.compute_node(node_idx, cut_size);
+                    if let Err(status) = self.check_limits(node_idx, &result, usage) {
+                        return status;
+                    }
+                    self.store_node(node_idx, result);
+                }
+                continue;
+            }
+            let chunk_size = level.len().div_ceil(self.threads).max(1);
+            let shared = SharedUsage {
+                start: usage.start,
+                total_cuts: AtomicUsize::new(usage.total_cuts),
+                memory: AtomicUsize::new(usage.memory),
+                stop: OnceLock::new(),
+            };
+            let this = &*self;
+            let shared = &shared;
+            // every thread returns the results of its chunk up to the node where a limit was hit
+            let results: Vec<Vec<NodeCuts>> = std::thread::scope(|scope| {
+                let handles: Vec<_> = level.chunks(chunk_size)
+                    .map(|chunk| scope.spawn(move || {
+                        let mut results = Vec::with_capacity(chunk.len());
+                        for &node_idx in chunk {
+                            if shared.stop.get().is_some() {
+                                break;
+                            }
+                            let result = this.compute_node(node_idx, cut_size);
+                            if let Err(status) = this.check_shared_limits(node_idx, &result, shared) {
+                                let _ = shared.stop.set(status);
+                                break;
+                            }
+                            results.push(result);
+                        }
+                        results
+                    }))
+                    .collect();
+                handles.into_iter().map(|handle| handle.join().expect("cut enumeration thread panicked")).collect()
+            });
+            for (chunk, chunk_results) in level.chunks(chunk_size).zip(results) {
+                for (&node_idx, result) in chunk.iter().zip(chunk_results) {
+                    if let Err(status) = self.check_limits(node_idx, &result, usage) {
+                        return status;
+                    }
+                    self.store_node(node_idx, result);
+                }
+            }
+            if let Some(&status) = shared.stop.get() {
+                return status;
+            }
+        }
+        EnumerationStatus::Complete
+    }
+
+    /// forget the results of the last run and prepare a new one
+    pub(crate) fn reset(&mut self, cut_size: usize) {
+        assert!(cut_size <= MAX_CUT_SIZE, "cut_size {cut_size} is larger than the maximum cut size {MAX_CUT_SIZE}");
+        assert!(self.cut_limit != Some(0), "cut_limit has to be at least 1 (the trivial cut is always kept)");
+        self.cuts.clear();
+        self.depth.clear();
+        self.area_flow.clear();
+        self.fanout_counts = self.aig.fanout_counts();
+        self.topo_order.clear();
+        self.skipped_nodes = 0;
+        self.status = EnumerationStatus::Complete;
+    }
+
+    /// store the result of compute_node
+    pub(crate) fn store_node(&mut self, node_idx: usize, result: NodeCuts) {
+        self.cuts.insert(node_idx, result.cuts);
+        self.depth.insert(node_idx, result.depth);
+        self.area_flow.insert(node_idx, result.area_flow);
+    }
+
+    /// Calculates the cuts, the depth and the area flow of a single node without changing anything,
+    /// so it can run for several nodes in parallel. All fanins of the node must have been processed before.
+    pub(crate) fn compute_node(&self, node_idx: usize, cut_size: usize) -> NodeCuts {
+        if !self.aig.node_map.contains_key(&node_idx) {
+            // it is not an AndNode -> Input
+            return NodeCuts { cuts: vec![self.trivial_cut(node_idx, 0, 0.0)], depth: 0, area_flow: 0.0, candidates: 1 };
+        }
+
+        // it is an AndNode
+        let new_cuts = self.compute_node_cuts(node_idx, cut_size);
+        let mut minimal = Self::filter_minimal_cuts(&new_cuts);
+
+        // attach the cost data to every cut. The priority cuts are selected with it.
+        for cut in minimal.iter_mut() {
+            cut.cost = Some(self.cut_cost(node_idx, cut));
+        }
+
+        let candidates = minimal.len() + 1;
+
+        // priority cuts: only keep the best cut_limit - 1 cuts, the last place is reserved for the trivial cut
+        if let Some(cut_limit) = self.cut_limit {
+            Self::sort_cuts(&mut minimal, self.sort_key);
+            minimal.truncate(cut_limit - 1);
+        }
+        let (depth, area_flow) = self.node_metrics(node_idx, &minimal);
+
+        // the node is always part of its own cut and is added at the end. 
+        // This is necessary for the cut_limit implementation: the trivial cut is never removed.
+        minimal.push(self.trivial_cut(node_idx, depth, area_flow));
+
+        NodeCuts { cuts: minimal, depth, area_flow, candidates }
+    }
+
+    /// The trivial cut {node}. Its truth table is the projection x_0. Its costs are the costs of
+    /// using the node as a leaf: the depth and area flow of the node, no exact area and one leaf.
+    fn trivial_cut(&self, node_idx: usize, depth: usize, area_flow: f64) -> Cut {
+        let mut cut = Cut::trivial(node_idx);
+        if self.compute_truth_tables {
+            cut.truth_table = Some(TruthTable::var(1, 0));
+        }
+        cut.cost = Some(CutCost { depth, area_flow, exact_area: 0, leaf_count: 1 });
+        cut
+    }
+
+    /// The depth and the area flow of a node. They are the best values of its (non-trivial) cuts.
+    /// If there is no such cut (for example for cut_size = 1) the fanins are used as the cut.
+    fn node_metrics(&self, node_idx: usize, cuts: &[Cut]) -> (usize, f64) {
+        let node = &self.aig.node_map[&node_idx];
+        let fanin_cut = Cut::from_leaves(&[node.left_signal.index(), node.right_signal.index()]);
+        let candidates = if cuts.is_empty() { std::slice::from_ref(&fanin_cut) } else { cuts };
+
+        let depth = candidates.iter().map(|cut| self.cut_depth(cut)).min().unwrap_or(0);
+        let area_flow = candidates.iter().map(|cut| self.cut_area_flow(cut)).fold(f64::INFINITY, f64::min);
+        let fanouts = self.fanout_counts.get(&node_idx).copied().unwrap_or(0).max(1);
+
+        (depth, area_flow / fanouts as f64)
+    }
+
+    /// depth of a cut: one level more than its deepest leaf
+    pub fn cut_depth(&self, cut: &Cut) -> usize {
+        1 + cut.iter().map(|leaf| self.depth.get(&leaf).copied().unwrap_or(0)).max().unwrap_or(0)
+    }
+
+    /// area flow of a cut: the AndNode itself plus the area flow of all leaves
+    pub fn cut_area_flow(&self, cut: &Cut) -> f64 {
+        1.0 + cut.iter().map(|leaf| self.area_flow.get(&leaf).copied().unwrap_or(0.0)).sum::<f64>()
+    }
+
+    /// Sort cuts by their cost data, best cut first. Ties are broken by the other metrics.
+    /// The sort is stable, so cuts that are equal in all metrics keep their order. Cuts without
+    /// cost data come last.
+    pub fn sort_cuts(cuts: &mut [Cut], sort_key: CutSortKey) {
+        cuts.sort_by(|a, b| {
+            let a = a.cost.as_ref().map_or([f64::INFINITY; 4], |cost| cost.priority(sort_key));
+            let b = b.cost.as_ref().map_or([f64::INFINITY; 4], |cost| cost.priority(sort_key));
+            a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+        });
+    }
+
+    /// All cuts with the nodes in increasing order (the leaves of every cut are always sorted).
+    /// In contrast to `cuts` this gives the same output on every run.
+    pub fn sorted_cuts(&self) -> BTreeMap<usize, &Vec<Cut>> {
+        self.cuts.iter().map(|(&node, cuts)| (node, cuts)).collect()
+    }
+
+    /// the leaves of a cut in increasing order
+    pub fn sorted_leaves(cut: &Cut) -> Vec<usize> {
+        cut.iter().collect()
+    }
+
+    /// compute all non-filtered cuts for a given node (including the cuts of its choices)
+    fn compute_node_cuts(&self, node_idx: usize, cut_size: usize) -> Vec<Cut> {
         let node = self.aig.node_map.get(&node_idx).unwrap();
         
         // get both fanins
-        let left = node.left_signal.index;
-        let right = node.right_signal.index;
+        let left = node.left_signal.index();
+        let right = node.right_signal.index();
         
         let mut new_cuts = Vec::new();
         
         // for each cut_l and for each cut_r union = cut_l ∪ cut_r
+        // merge returns None if |union| > cut_size, so only small enough unions are added to new_cuts
         for cut_l in &self.cuts[&left] {
             for cut_r in &self.cuts[&right] {
-                let union: HashSet<_> = cut_l.union(cut_r).cloned().collect();
-
-                // add union only to new_cuts if |union| <= cut_size
-                if union.len() <= cut_size {
+                if let Some(mut union) = cut_l.merge(cut_r, cut_size) {
+                    if self.compute_truth_tables {
+                        union.truth_table = Self::merge_truth_tables(cut_l, node.left_signal, cut_r, node.right_signal, &union);
+                    }
                     new_cuts.push(union);
                 }
             }
         }
 
+        // structural choices: the alternatives compute the same function, so their cuts are also cuts
+        // of this node. They are processed before this node (see topological_sort). Only the trivial
+        // cut of an alternative is skipped.
+        // If the choice is complemented, the truth tables of its cuts have to be inverted.
+        for choice in self.aig.choices_of(node_idx) {
+            if let Some(choice_cuts) = self.cuts.get(&choice.index()) {
+                for cut in choice_cuts.iter().filter(|cut| !cut.contains(choice.index())) {
+                    let mut cut = cut.clone();
+                    cut.truth_table = cut.truth_table.map(|tt| tt.complement(choice.is_complemented()));
+                    new_cuts.push(cut);
+                }
+            }
+        }
+
         new_cuts
     }
 
+    /// Truth table of an AndNode over the leaves of `union`. The truth tables of both fanin cuts are
+    /// expanded to the leaves of the union, inverted if the fanin edge is inverted and combined with and.
+    fn merge_truth_tables(cut_l: &Cut, left: Signal, cut_r: &Cut, right: Signal, union: &Cut) -> Option<TruthTable> {
+        let tt_l = cut_l.truth_table.as_ref()?.expand(cut_l.leaves(), union.leaves()).complement(left.is_complemented());
+        let tt_r = cut_r.truth_table.as_ref()?.expand(cut_r.leaves(), union.leaves()).complement(right.is_complemented());
+        Some(tt_l.and(&tt_r))
+    }
+
     /// filter the cuts. No cut is dominated by another cut
-    /// c_1 dominates c_2 if c_1 ⊂ c_2 (we take c_1)
-    fn filter_minimal_cuts(all_cuts: &[HashSet<usize>]) -> Vec<HashSet<usize>> {
-    let mut result = Vec::new();
+    /// c_1 dominates c_2 if c_1 ⊆ c_2 (we take c_1)
+    /// Every new cut is compared with the cuts that are already minimal. Most comparisons are
+    /// rejected by the signature alone (see Cut::dominates). Duplicates are dominated as well.
+    fn filter_minimal_cuts(all_cuts: &[Cut]) -> Vec<Cut> {
+        let mut result: Vec<Cut> = Vec::new();
 
-    'outer: for (i, c_2) in all_cuts.iter().enumerate() {
-        // check if 
-        for (j, c_1) in all_cuts.iter().enumerate() {
-            if i != j && c_1.is_subset(c_2) && c_1 != c_2 {
-                // c_2 wird von c_1 dominiert -> nicht minimal
-                continue 'outer;
+        for c_2 in all_cuts {
+            if result.iter().any(|c_1| c_1.dominates(c_2)) {
+                // c_2 is dominated by a cut that we already have -> not minimal
+                continue;
             }
-        }
-        // Prüfe auf Duplikate in result
-        if !result.iter().any(|x| x == c_2) {
+            // c_2 may dominate cuts that we kept so far
+            result.retain(|c_1| !c_2.dominates(c_1));
             result.push(c_2.clone());
         }
+        result
     }
-    result
 }
 
-}
\ No newline at end of file
+#[cfg(test)]
+mod tests {
+    use super::{CutEnumerator, CutSortKey, MIN_PARALLEL_LEVEL_SIZE};
+    use crate::aig_structure::aig::AIG;
+    use crate::aig_structure::signal::Signal;
+    use crate::algorithms::cut_limits::{estimated_memory, CutLimits, EnumerationStatus};
+    use crate::input_output::read_aiger::AigerReader;
+    use std::time::Duration;
+
+    /// An AIG with 5 levels of 200 AndNodes each, so the parallel mode really starts threads. Every node
+    /// uses two random nodes of the level below (or inputs) with random complements.
+    fn wide_aig() -> AIG {
+        let mut seed = 11u64;
+        let mut random = |n: usize| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            (seed % n as u64) as usize
+        };
+        let mut aig = AIG::new();
+        let mut below: Vec<Signal> = (1..=100).map(|index| aig.add_input(index)).collect();
+        let mut next_index = 101;
+        for _ in 0..5 {
+            let mut level = Vec::new();
+            while level.len() < 200 {
+                let a = below[random(below.len())] ^ (random(2) == 1);
+                let b = below[random(below.len())] ^ (random(2) == 1);
+                let signal = aig.create_and(a, b, next_index);
+                if signal.index() == next_index {
+                    next_index += 1;
+                    level.push(signal);
+                }
+            }
+            below = level;
+        }
+        for signal in below {
+            aig.add_output(signal);
+        }
+        aig
+    }
+
+    fn enumerate(aig: &AIG, threads: usize, limits: CutLimits) -> CutEnumerator<'_> {
+        let mut cut_enumerator = CutEnumerator::new(aig);
+        cut_enumerator.threads = threads;
+        cut_enumerator.compute_truth_tables = true;
+        cut_enumerator.cut_limit = Some(8);
+        cut_enumerator.sort_key = CutSortKey::AreaFlow;
+        cut_enumerator.limits = limits;
+        cut_enumerator.enumerate_cuts(4, &aig.inputs);
+        cut_enumerator
+    }
+
+    #[test]
+    fn parallel_enumeration_equals_sequential() {
+        let aig = wide_aig();
+        assert!(aig.node_map.len() >= 5 * MIN_PARALLEL_LEVEL_SIZE);
+        let sequential = enumerate(&aig, 1, CutLimits::default());
+        for threads in [2, 3, 8] {
+            let parallel = enumerate(&aig, threads, CutLimits::default());
+            assert!(parallel.status.is_complete());
+            assert_eq!(parallel.sorted_cuts(), sequential.sorted_cuts(), "{threads} threads");
+            assert_eq!(parallel.depth, sequential.depth);
+            assert_eq!(parallel.area_flow, sequential.area_flow);
+        }
+    }
+
+    #[test]
+    fn parallel_enumeration_stops_at_the_limits() {
+        let aig = wide_aig();
+        let stored_memory = |cut_enumerator: &CutEnumerator| cut_enumerator.cuts.values().map(|cuts| estimated_memory(cuts)).sum::<usize>();
+
+        // the memory of about one and a half levels
+        let max_memory = stored_memory(&enumerate(&aig, 1, CutLimits::default())) * 3 / 10;
+        let limits = CutLimits { max_memory: Some(max_memory), ..CutLimits::default() };
+        let parallel = enumerate(&aig, 4, limits);
+        assert_eq!(parallel.status, EnumerationStatus::MemoryLimit);
+        assert!(stored_memory(&parallel) <= max_memory);
+
+        let limits = CutLimits { max_total_cuts: Some(1000), ..CutLimits::default() };
+        let parallel = enumerate(&aig, 4, limits);
+        assert_eq!(parallel.status, EnumerationStatus::TotalCutLimit);
+        assert!(parallel.cuts.values().map(Vec::len).sum::<usize>() <= 1000);
+    }
+
+    #[test]
+    fn enumeration_stops_at_the_limits() {
+        let aig = wide_aig();
+        let complete = enumerate(&aig, 1, CutLimits::default());
+        let total_cuts = complete.cuts.values().map(Vec::len).sum::<usize>();
+
+        // the first AndNode already has more than one minimal cut (its fanins and itself)
+        let limits = CutLimits { max_cuts_per_node: Some(1), ..CutLimits::default() };
+        let stopped = enumerate(&aig, 1, limits);
+        let EnumerationStatus::NodeCutLimit { node } = stopped.status else {
+            panic!("unexpected status {}", stopped.status);
+        };
+        assert!(aig.node_map.contains_key(&node));
+        assert!(!stopped.cuts.contains_key(&node));
+
+        let limits = CutLimits { max_total_cuts: Some(total_cuts / 2), ..CutLimits::default() };
+        let stopped = enumerate(&aig, 1, limits);
+        assert_eq!(stopped.status, EnumerationStatus::TotalCutLimit);
+        assert!(stopped.cuts.values().map(Vec::len).sum::<usize>() <= total_cuts / 2);
+
+        let limits = CutLimits { max_memory: Some(1), ..CutLimits::default() };
+        assert_eq!(enumerate(&aig, 1, limits).status, EnumerationStatus::MemoryLimit);
+
+        let limits = CutLimits { max_time: Some(Duration::ZERO), ..CutLimits::default() };
+        let stopped = enumerate(&aig, 1, limits);
+        assert_eq!(stopped.status, EnumerationStatus::TimeLimit);
+        assert!(stopped.cuts.len() < complete.cuts.len());
+
+        // limits that are not reached do not change the result
+        let limits = CutLimits {
+            max_time: Some(Duration::from_secs(3600)),
+            max_total_cuts: Some(total_cuts),
+            max_cuts_per_node: Some(usize::MAX),
+            max_memory: Some(usize::MAX),
+        };
+        let unlimited = enumerate(&aig, 1, limits);
+        assert!(unlimited.status.is_complete());
+        assert_eq!(unlimited.sorted_cuts(), complete.sorted_cuts());
+    }
+
+    /// the cuts, depths and area flows of update_cuts after an edit must be the same as after a new run
+    fn assert_update_equals_new_run(edit: fn(&mut AIG) -> Vec<usize>, cut_limit: Option<usize>, sort_key: CutSortKey) -> usize {
+        let aig = AigerReader::from_file("debug/test_files/aigs/aigverse_1.aig").unwrap().aig;
+        let configure = |cut_enumerator: &mut CutEnumerator| {
+            cut_enumerator.cut_limit = cut_limit;
+            cut_enumerator.sort_key = sort_key;
+            cut_enumerator.compute_truth_tables = true;
+        };
+        let mut cut_enumerator = CutEnumerator::new(&aig);
+        configure(&mut cut_enumerator);
+        cut_enumerator.enumerate_cuts(4, &aig.inputs);
+
+        let mut edited = aig.clone();
+        let changed = edit(&mut edited);
+        let report = edited.check();
+        assert!(report.is_ok(), "{report}");
+        let mut updated = cut_enumerator.rebind(&edited);
+        let recomputed = updated.update_cuts(4, &changed);
+
+        let mut fresh = CutEnumerator::new(&edited);
+        configure(&mut fresh);
+        fresh.enumerate_cuts(4, &edited.inputs);
+        assert_eq!(updated.sorted_cuts(), fresh.sorted_cuts());
+        assert_eq!(updated.depth, fresh.depth);
+        assert_eq!(updated.area_flow, fresh.area_flow);
+        assert!(updated.status.is_complete());
+        recomputed
+    }
+
+    /// a new gate over the two highest AndNodes drives the first output
+    fn add_gate(aig: &mut AIG) -> Vec<usize> {
+        let mut node_ids: Vec<usize> = aig.node_map.keys().copied().collect();
+        node_ids.sort_unstable();
+        let (a, b) = (node_ids[node_ids.len() - 2], node_ids[node_ids.len() - 1]);
+        let new_node = aig.create_and(Signal::new(a, false), Signal::new(b, true), b + 1);
+        aig.outputs[0] = new_node;
+        vec![new_node.index()]
+    }
+
+    /// a fanin of an AndNode in the middle of the graph is replaced by an input
+    fn replace_fanin(aig: &mut AIG) -> Vec<usize> {
+        let mut node_ids: Vec<usize> = aig.node_map.keys().copied().collect();
+        node_ids.sort_unstable();
+        let node_idx = node_ids[node_ids.len() / 2];
+        let left = aig.node_map[&node_idx].left_signal;
+        let input = if left.index() == 1 { 2 } else { 1 };
+        assert!(aig.replace_fanins(node_idx, left, Signal::new(input, true)));
+        vec![node_idx]
+    }
+
+    #[test]
+    fn update_cuts_equals_new_run() {
+        for (cut_limit, sort_key) in [(None, CutSortKey::Size), (Some(4), CutSortKey::ExactArea), (Some(3), CutSortKey::AreaFlow)] {
+            assert_update_equals_new_run(add_gate, cut_limit, sort_key);
+            assert_update_equals_new_run(replace_fanin, cut_limit, sort_key);
+        }
+    }
+
+    #[test]
+    fn update_cuts_stops_where_the_edit_has_no_effect() {
+        let aig = AigerReader::from_file("debug/test_files/aigs/aigverse_1.aig").unwrap().aig;
+        let recomputed = assert_update_equals_new_run(add_gate, None, CutSortKey::Size);
+        assert!(recomputed < aig.node_map.len() / 10, "{recomputed} of {} nodes were computed again", aig.node_map.len());
+    }
+}
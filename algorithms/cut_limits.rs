@@ -0,0 +1,68 @@
+use crate::algorithms::cut::Cut;
+use crate::algorithms::truth_table::TruthTable;
+use std::fmt;
+use std::time::Duration;
+
+/// Resource limits for the cut enumeration. A limit that is None is not checked.
+/// max_time: wall-clock time of one run
+/// max_total_cuts: number of cuts of all nodes together
+/// max_cuts_per_node: number of minimal cuts of a single node before the cut_limit is applied. Unlike the
+///                    cut_limit, which keeps the best cuts and goes on, this stops the run. It catches the
+///                    nodes where the enumeration blows up.
+/// max_memory: estimated memory of all cuts in bytes (see estimated_memory)
+///
+/// The limits are checked after every node (with several threads: by every thread against the usage of
+/// all threads together). When a limit is hit, the run stops and the cuts of all nodes that were processed
+/// before are kept. Because the nodes are processed in topological order, these cuts are complete. See
+/// CutEnumerator::status.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CutLimits {
+    pub max_time: Option<Duration>,
+    pub max_total_cuts: Option<usize>,
+    pub max_cuts_per_node: Option<usize>,
+    pub max_memory: Option<usize>,
+}
+
+/// Result of the last run of the cut enumeration.
+/// Complete: all nodes were processed
+/// TimeLimit, TotalCutLimit, MemoryLimit: the run stopped because this limit was hit
+/// NodeCutLimit: the run stopped because this node has too many cuts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EnumerationStatus {
+    #[default]
+    Complete,
+    TimeLimit,
+    TotalCutLimit,
+    NodeCutLimit { node: usize },
+    MemoryLimit,
+}
+
+impl EnumerationStatus {
+    pub fn is_complete(&self) -> bool {
+        *self == EnumerationStatus::Complete
+    }
+}
+
+impl fmt::Display for EnumerationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EnumerationStatus::Complete => write!(f, "complete"),
+            EnumerationStatus::TimeLimit => write!(f, "time limit reached"),
+            EnumerationStatus::TotalCutLimit => write!(f, "total cut limit reached"),
+            EnumerationStatus::NodeCutLimit { node } => write!(f, "cut limit per node reached at node {node}"),
+            EnumerationStatus::MemoryLimit => write!(f, "memory limit reached"),
+        }
+    }
+}
+
+/// Estimated memory of cuts in bytes: the cuts themselves and the words of truth tables with more
+/// than 6 variables (smaller truth tables are stored inside the cut).
+pub fn estimated_memory(cuts: &[Cut]) -> usize {
+    let heap_words: usize = cuts.iter()
+        .filter_map(|cut| match &cut.truth_table {
+            Some(TruthTable::Dynamic { words, .. }) => Some(words.len()),
+            _ => None,
+        })
+        .sum();
+    std::mem::size_of_val(cuts) + heap_words * std::mem::size_of::<u64>()
+}
@@ -0,0 +1,95 @@
+use crate::algorithms::cut::Cut;
+use crate::algorithms::cut_enumerator::{CutEnumerator, Usage};
+use crate::algorithms::cut_limits::{estimated_memory, EnumerationStatus};
+use std::collections::HashMap;
+
+/// Result of CutEnumerator::visit_cuts.
+/// peak_cuts: the largest number of cuts that were stored at the same time
+/// status: whether all nodes were visited or which limit stopped the run (also stored in `status`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VisitSummary {
+    pub peak_cuts: usize,
+    pub status: EnumerationStatus,
+}
+
+impl<'a> CutEnumerator<'a> {
+    /// Streaming version of enumerate_cuts: the visitor is called once for every node with its cuts, in
+    /// topological order (the same nodes as in enumerate_cuts). The cuts of a node are only kept until
+    /// all of its fanouts (and the representative if it is a choice) are processed, so the peak memory
+    /// is bounded by the frontier of the topological order and not by the whole design. After the run
+    /// `cuts` is empty, depth and area_flow are still available for all nodes.
+    /// cut_size: maximum number of leaves for a cut.
+    /// visitor: gets the node and its cuts
+    /// Returns the peak number of stored cuts and the status of the run.
+    ///
+    /// The limits are checked for every node like in enumerate_cuts. The run stops before the visitor gets
+    /// the node that exceeded a limit, so the visited nodes are a partial result then. max_total_cuts counts
+    /// all cuts that were computed, max_memory only the cuts that are stored at the same time.
+    /// The nodes are processed on the current thread, `threads` is not used.
+    pub fn visit_cuts<F: FnMut(usize, &[Cut])>(&mut self, cut_size: usize, mut visitor: F) -> VisitSummary {
+        self.reset(cut_size);
+        let roots: Vec<usize> = self.default_roots().into_iter().filter(|&root| root != 0).collect();
+        self.topo_order = self.aig.topological_sort_from(&roots);
+        if self.topo_order.is_empty() {
+            self.topo_order = self.aig.inputs.iter().map(|sig| sig.index()).collect();
+        }
+
+        // remaining_uses: how many nodes of topo_order still need the cuts of a node
+        let mut remaining_uses: HashMap<usize, usize> = HashMap::new();
+        for node_idx in &self.topo_order {
+            for fanin in self.cut_sources(*node_idx) {
+                *remaining_uses.entry(fanin).or_insert(0) += 1;
+            }
+        }
+
+        let mut stored_cuts = 0;
+        let mut peak_cuts = 0;
+        let mut usage = Usage::new();
+        for node_idx in self.topo_order.clone() {
+            let result = self.compute_node(node_idx, cut_size);
+            if let Err(status) = self.check_limits(node_idx, &result, &mut usage) {
+                self.status = status;
+                break;
+            }
+            self.store_node(node_idx, result);
+            let cuts = &self.cuts[&node_idx];
+            stored_cuts += cuts.len();
+            peak_cuts = peak_cuts.max(stored_cuts);
+            visitor(node_idx, cuts);
+
+            // free the cuts of all nodes that are not needed anymore (the node itself if nothing uses it)
+            let mut done = Vec::new();
+            for fanin in self.cut_sources(node_idx) {
+                let uses = remaining_uses.get_mut(&fanin).expect("every fanin is counted");
+                *uses -= 1;
+                if *uses == 0 {
+                    done.push(fanin);
+                }
+            }
+            if !remaining_uses.contains_key(&node_idx) {
+                done.push(node_idx);
+            }
+            for node in done {
+                if let Some(cuts) = self.cuts.remove(&node) {
+                    stored_cuts -= cuts.len();
+                    if self.limits.max_memory.is_some() {
+                        usage.memory -= estimated_memory(&cuts);
+                    }
+                }
+            }
+        }
+
+        self.count_skipped_nodes();
+        VisitSummary { peak_cuts, status: self.status }
+    }
+
+    /// the nodes whose cuts are needed to compute the cuts of a node: its fanins and its choices
+    fn cut_sources(&self, node_idx: usize) -> Vec<usize> {
+        let Some(node) = self.aig.node_map.get(&node_idx) else {
+            return Vec::new();
+        };
+        let mut sources = vec![node.left_signal.index(), node.right_signal.index()];
+        sources.extend(self.aig.choices_of(node_idx).iter().map(|choice| choice.index()));
+        sources
+    }
+}
@@ -0,0 +1,71 @@
+use crate::algorithms::cut::Cut;
+use crate::algorithms::cut_enumerator::CutEnumerator;
+use std::collections::{HashMap, HashSet};
+
+/// The logic between a node and the leaves of one of its cuts.
+/// internal: the AndNodes strictly between the root and the leaves (neither the root nor the leaves), sorted
+/// external_fanout: the internal nodes that also have fanouts outside the cone (AndNodes that are not part
+///                  of the cone, outputs or latches), sorted
+/// fanout_free: true if no internal node has an external fanout. Then the whole cone is removed if the
+///              root is implemented with this cut, otherwise the external_fanout nodes (and their cones) stay.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CutVolume {
+    pub internal: Vec<usize>,
+    pub external_fanout: Vec<usize>,
+    pub fanout_free: bool,
+}
+
+impl CutVolume {
+    /// number of internal nodes
+    pub fn len(&self) -> usize {
+        self.internal.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.internal.is_empty()
+    }
+}
+
+impl<'a> CutEnumerator<'a> {
+    /// Compute the volume of a cut of the given node. The cone is the root and all AndNodes that are reached
+    /// from the root without passing a leaf. The number of fanouts from inside the cone is compared with the
+    /// number of all fanouts (fanout_counts of the last run) to find the nodes with external fanouts.
+    /// For a cut of a choice, the cone is the structure that the cut was built from (see CutCone), with
+    /// the chosen alternative as root.
+    /// The trivial cut has no volume.
+    pub fn cut_volume(&self, node_idx: usize, cut: &Cut) -> CutVolume {
+        if cut.contains(node_idx) {
+            return CutVolume { fanout_free: true, ..CutVolume::default() };
+        }
+
+        // internal fanouts: how often a node is used as a fanin by the root and the internal nodes
+        let cone = self.cut_cone(node_idx, cut);
+        let mut internal_fanouts: HashMap<usize, usize> = HashMap::new();
+        let mut visited: HashSet<usize> = HashSet::from([cone.root]);
+        let mut stack = vec![cone.root];
+        while let Some(current) = stack.pop() {
+            if !self.aig.node_map.contains_key(&current) {
+                continue;
+            }
+            for fanin in cone.fanins(self, current) {
+                if cut.contains(fanin) || !self.aig.node_map.contains_key(&fanin) {
+                    continue;
+                }
+                *internal_fanouts.entry(fanin).or_insert(0) += 1;
+                if visited.insert(fanin) {
+                    stack.push(fanin);
+                }
+            }
+        }
+
+        let mut internal: Vec<usize> = internal_fanouts.keys().copied().collect();
+        internal.sort_unstable();
+        let external_fanout: Vec<usize> = internal.iter()
+            .copied()
+            .filter(|node| self.fanout_counts.get(node).copied().unwrap_or(0) > internal_fanouts[node])
+            .collect();
+        let fanout_free = external_fanout.is_empty();
+
+        CutVolume { internal, external_fanout, fanout_free }
+    }
+}
@@ -0,0 +1,228 @@
+use crate::aig_structure::aig::AIG;
+use std::collections::{HashMap, HashSet};
+
+/// Direction of a dominator tree.
+/// Outputs: x dominates y if every path from y to an output (or latch next state) goes through x.
+///          The nodes dominated by x form a region whose only connection to the outputs is x.
+/// Inputs: x dominates y if every path from an input (or latch, or the constant) to y goes through x
+///         (post-dominators from the inputs).
+/// Every node dominates itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DominatorKind {
+    Outputs,
+    Inputs,
+}
+
+/// Dominator tree of an AIG in one direction.
+/// kind: the direction
+/// idom: immediate dominator of every node. None means that there is no real dominator: the node is only
+///       dominated by the virtual root, which is connected to all outputs (Outputs) or all inputs (Inputs).
+/// depth: depth of every node in the tree, the virtual root has depth 0
+///
+/// Outputs: only the nodes in the transitive fanin of the outputs and latches are in the tree.
+/// Choices are not taken into account: the paths only follow the fanins, so an alternative that is not used
+/// by any other node is not in the Outputs tree.
+#[derive(Debug, Clone)]
+pub struct DominatorTree {
+    pub kind: DominatorKind,
+    pub idom: HashMap<usize, Option<usize>>,
+    depth: HashMap<usize, usize>,
+}
+
+impl DominatorTree {
+    /// Compute the immediate dominators (Cooper, Harvey and Kennedy). The AIG is acyclic, so a single pass in
+    /// topological order (Inputs) or reverse topological order (Outputs) is enough: when a node is processed,
+    /// all of its predecessors are done and its immediate dominator is the nearest common ancestor of the
+    /// predecessors in the tree.
+    pub fn new(aig: &AIG, kind: DominatorKind) -> Self {
+        let mut tree = DominatorTree { kind, idom: HashMap::new(), depth: HashMap::new() };
+        match kind {
+            DominatorKind::Inputs => tree.compute_input_dominators(aig),
+            DominatorKind::Outputs => tree.compute_output_dominators(aig),
+        }
+        tree
+    }
+
+    /// predecessors are the fanins, the sources are the inputs, latches and the constant
+    fn compute_input_dominators(&mut self, aig: &AIG) {
+        let sources = aig.inputs.iter().map(|input| input.index())
+            .chain(aig.latches.iter().map(|latch| latch.index))
+            .chain([0]);
+        for source in sources {
+            self.insert(source, None);
+        }
+
+        for node_idx in aig.topological_sort() {
+            let Some(node) = aig.node_map.get(&node_idx) else {
+                continue;
+            };
+            let idom = self.common_dominator([node.left_signal.index(), node.right_signal.index()].into_iter());
+            self.insert(node_idx, idom);
+        }
+    }
+
+    /// predecessors are the fanouts, the outputs and latch next states are connected to the virtual root
+    fn compute_output_dominators(&mut self, aig: &AIG) {
+        let sinks: Vec<usize> = aig.outputs.iter()
+            .chain(aig.latches.iter().map(|latch| &latch.next))
+            .map(|signal| signal.index())
+            .filter(|&sink| sink != 0)
+            .collect();
+        // topological_sort_from also follows the choices. An alternative is not a fanin of any node, so only
+        // the nodes that are reached through the fanins are kept, otherwise a node would have no fanout here.
+        let mut reached: HashSet<usize> = sinks.iter().copied().collect();
+        let mut stack = sinks.clone();
+        while let Some(node_idx) = stack.pop() {
+            if let Some(node) = aig.node_map.get(&node_idx) {
+                for fanin in [node.left_signal.index(), node.right_signal.index()] {
+                    if reached.insert(fanin) {
+                        stack.push(fanin);
+                    }
+                }
+            }
+        }
+        let order: Vec<usize> = aig.topological_sort_from(&sinks).into_iter()
+            .filter(|node_idx| reached.contains(node_idx))
+            .collect();
+        let sinks: HashSet<usize> = sinks.into_iter().collect();
+
+        // fanouts inside the transitive fanin of the sinks
+        let mut fanouts: HashMap<usize, Vec<usize>> = HashMap::new();
+        for &node_idx in &order {
+            if let Some(node) = aig.node_map.get(&node_idx) {
+                for fanin in [node.left_signal.index(), node.right_signal.index()] {
+                    fanouts.entry(fanin).or_default().push(node_idx);
+                }
+            }
+        }
+
+        for &node_idx in order.iter().rev() {
+            let idom = if sinks.contains(&node_idx) {
+                // a path ends here, only the virtual root dominates the node
+                None
+            } else {
+                self.common_dominator(fanouts[&node_idx].iter().copied())
+            };
+            self.insert(node_idx, idom);
+        }
+    }
+
+    fn insert(&mut self, node_idx: usize, idom: Option<usize>) {
+        let depth = idom.map_or(0, |idom| self.depth[&idom]) + 1;
+        self.idom.insert(node_idx, idom);
+        self.depth.insert(node_idx, depth);
+    }
+
+    /// The nearest common dominator of all predecessors (each predecessor dominates itself). Once it is
+    /// the virtual root, the other predecessors do not matter anymore.
+    fn common_dominator(&self, mut predecessors: impl Iterator<Item = usize>) -> Option<usize> {
+        let first = predecessors.next()?;
+        predecessors.try_fold(first, |a, b| self.intersect(Some(a), Some(b)))
+    }
+
+    /// nearest common ancestor of two nodes in the tree (None is the virtual root)
+    fn intersect(&self, mut a: Option<usize>, mut b: Option<usize>) -> Option<usize> {
+        let depth = |node: Option<usize>| node.map_or(0, |node| self.depth[&node]);
+        while a != b {
+            if depth(a) >= depth(b) {
+                a = a.and_then(|node| self.idom[&node]);
+            } else {
+                b = b.and_then(|node| self.idom[&node]);
+            }
+        }
+        a
+    }
+
+    /// the immediate dominator of a node, None if the node is only dominated by the virtual root or is not in the tree
+    pub fn immediate_dominator(&self, node_idx: usize) -> Option<usize> {
+        self.idom.get(&node_idx).copied().flatten()
+    }
+
+    /// true if x dominates y. Every node in the tree dominates itself.
+    pub fn dominates(&self, x: usize, y: usize) -> bool {
+        let (Some(&depth_x), Some(&depth_y)) = (self.depth.get(&x), self.depth.get(&y)) else {
+            return false;
+        };
+        let mut current = y;
+        for _ in depth_x..depth_y {
+            match self.idom[&current] {
+                Some(idom) => current = idom,
+                None => return false,
+            }
+        }
+        current == x
+    }
+
+    /// all nodes that are dominated by x (x included) in increasing order
+    pub fn dominated_by(&self, x: usize) -> Vec<usize> {
+        let mut nodes: Vec<usize> = self.idom.keys().copied().filter(|&y| self.dominates(x, y)).collect();
+        nodes.sort_unstable();
+        nodes
+    }
+
+    /// all (node, immediate dominator) pairs with a real dominator, sorted by the node
+    pub fn edges(&self) -> Vec<(usize, usize)> {
+        let mut edges: Vec<(usize, usize)> = self.idom.iter()
+            .filter_map(|(&node, &idom)| idom.map(|idom| (node, idom)))
+            .collect();
+        edges.sort_unstable();
+        edges
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DominatorKind, DominatorTree};
+    use crate::aig_structure::aig::AIG;
+    use crate::aig_structure::signal::Signal;
+
+    /// x7 = (x1 and x2) and (x2 and x3), x8 = x7 and x4, x9 = x7 and !x4, x10 = x8 and x9 is the only output.
+    /// The paths of x2 reconverge at x7, the paths of x7 and x4 at x10.
+    fn example() -> AIG {
+        let mut aig = AIG::new();
+        let x: Vec<Signal> = (1..=4).map(|index| aig.add_input(index)).collect();
+        let x5 = aig.create_and(x[0], x[1], 5);
+        let x6 = aig.create_and(x[1], x[2], 6);
+        let x7 = aig.create_and(x5, x6, 7);
+        let x8 = aig.create_and(x7, x[3], 8);
+        let x9 = aig.create_and(x7, !x[3], 9);
+        let x10 = aig.create_and(x8, x9, 10);
+        aig.add_output(x10);
+        aig
+    }
+
+    #[test]
+    fn output_dominators_of_a_reconvergent_circuit() {
+        let tree = DominatorTree::new(&example(), DominatorKind::Outputs);
+        // x1 and x3 have one fanout each, x2, x7 and x4 reconverge
+        let expected = vec![(1, 5), (2, 7), (3, 6), (4, 10), (5, 7), (6, 7), (7, 10), (8, 10), (9, 10)];
+        assert_eq!(tree.edges(), expected);
+        assert_eq!(tree.immediate_dominator(10), None);
+        assert!(tree.dominates(7, 2) && tree.dominates(10, 1) && !tree.dominates(5, 2));
+        assert_eq!(tree.dominated_by(7), vec![1, 2, 3, 5, 6, 7]);
+    }
+
+    #[test]
+    fn input_dominators_of_a_reconvergent_circuit() {
+        let tree = DominatorTree::new(&example(), DominatorKind::Inputs);
+        // x5, x6 and x7 reach two different inputs, x8, x9 and x10 reach x4 without passing x7
+        assert_eq!(tree.edges(), vec![]);
+        assert!(tree.dominates(7, 7) && !tree.dominates(7, 8) && !tree.dominates(1, 5));
+        assert_eq!(tree.dominated_by(7), vec![7]);
+    }
+
+    #[test]
+    fn choices_are_not_part_of_the_output_tree() {
+        // x11 = x6 and x5 is an alternative of x7 (same function, different structure) without fanouts
+        let mut aig = example();
+        let x11 = aig.create_and(Signal::new(6, false), Signal::new(5, true), 11);
+        assert!(aig.add_choice(7, x11));
+        let with_choice = DominatorTree::new(&aig, DominatorKind::Outputs);
+        assert_eq!(with_choice.edges(), DominatorTree::new(&example(), DominatorKind::Outputs).edges());
+        assert!(!with_choice.idom.contains_key(&11));
+
+        // the Inputs tree contains all nodes, the alternative as well
+        let tree = DominatorTree::new(&aig, DominatorKind::Inputs);
+        assert_eq!(tree.immediate_dominator(11), None);
+    }
+}
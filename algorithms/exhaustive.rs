@@ -0,0 +1,102 @@
+use crate::aig_structure::aig::AIG;
+use crate::algorithms::simulator::Simulator;
+use crate::algorithms::truth_table::TruthTable;
+use crate::error::{CrustError, Result};
+use std::str::FromStr;
+
+/// Maximum number of inputs (and latches) for the exhaustive simulation: 2^16 patterns are 1024 words per node.
+pub const MAX_EXHAUSTIVE_VARS: usize = 16;
+
+/// How truth tables are printed: hexadecimal (0x8) or binary (0b1000), the highest minterm first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TruthTableFormat {
+    #[default]
+    Hex,
+    Binary,
+}
+
+impl TruthTableFormat {
+    pub fn format(&self, truth_table: &TruthTable) -> String {
+        match self {
+            TruthTableFormat::Hex => format!("0x{}", truth_table.to_hex()),
+            TruthTableFormat::Binary => format!("0b{}", truth_table.to_binary()),
+        }
+    }
+}
+
+impl FromStr for TruthTableFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "hex" => Ok(TruthTableFormat::Hex),
+            "binary" | "bin" => Ok(TruthTableFormat::Binary),
+            _ => Err(format!("unknown truth table format '{s}' (expected hex or binary)")),
+        }
+    }
+}
+
+/// Complete truth table of every output. The variables are the inputs followed by the latch outputs (latches
+/// are free variables) in AIGER order, so variable i is the i-th input. All 2^n input assignments are simulated
+/// at once: the pattern of variable i is the projection function x_i, so the value of an output for all
+/// patterns is its truth table.
+/// Returns an error if there are more than MAX_EXHAUSTIVE_VARS variables.
+pub fn output_truth_tables(aig: &AIG) -> Result<Vec<TruthTable>> {
+    let sources: Vec<usize> = aig.inputs.iter().map(|input| input.index())
+        .chain(aig.latches.iter().map(|latch| latch.index))
+        .collect();
+    let num_vars = sources.len();
+    if num_vars > MAX_EXHAUSTIVE_VARS {
+        return Err(CrustError::InvalidArgument(format!(
+            "exhaustive simulation supports at most {MAX_EXHAUSTIVE_VARS} inputs and latches, the AIG has {num_vars}"
+        )));
+    }
+
+    let num_words = if num_vars <= TruthTable::MAX_WORD_VARS { 1 } else { 1 << (num_vars - TruthTable::MAX_WORD_VARS) };
+    let mut simulator = Simulator::new(aig, num_words);
+    for (var, &source) in sources.iter().enumerate() {
+        simulator.set_pattern(source, TruthTable::var(num_vars, var).words().to_vec());
+    }
+    simulator.run();
+
+    // the patterns of less than 6 variables are repeated over the whole word, so are the results
+    let truth_tables = simulator.output_values().into_iter()
+        .map(|words| {
+            if num_vars <= TruthTable::MAX_WORD_VARS {
+                TruthTable::Word { num_vars: num_vars as u8, bits: words[0] }
+            } else {
+                TruthTable::Dynamic { num_vars: num_vars as u8, words }
+            }
+        })
+        .collect();
+    Ok(truth_tables)
+}
+
+/// Parse expected truth tables, one output per line. Only the last word of a line is used, so the report
+/// "output 0: 0x8" is accepted as well as "0x8" or "8". Words with the prefix "0b" are binary, all others
+/// hexadecimal. Empty lines and lines that start with '#' are skipped.
+pub fn parse_truth_tables(text: &str, num_vars: usize) -> Result<Vec<TruthTable>> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let word = line.split_whitespace().last().unwrap_or(line);
+            let truth_table = match word.strip_prefix("0b") {
+                Some(binary) => TruthTable::from_binary(num_vars, binary),
+                None => TruthTable::from_hex(num_vars, word),
+            };
+            truth_table.ok_or_else(|| CrustError::Parse {
+                what: format!("truth table with {num_vars} variables"),
+                value: word.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// The outputs whose truth table differs from the expected one. Outputs without an expected truth table
+/// (or expected truth tables without an output) count as different.
+pub fn compare_truth_tables(actual: &[TruthTable], expected: &[TruthTable]) -> Vec<usize> {
+    (0..actual.len().max(expected.len()))
+        .filter(|&i| actual.get(i) != expected.get(i))
+        .collect()
+}
@@ -0,0 +1,147 @@
+use crate::aig_structure::aig::AIG;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Maximum fanout-free cone (MFFC) of a node: all AndNodes that are only used by the node itself, so they
+/// would be deleted together with the node.
+/// root: the node
+/// nodes: the AndNodes of the MFFC in increasing order, the root included
+/// leaves: the nodes that feed the MFFC but are not part of it (inputs, latches and shared AndNodes) in
+///         increasing order. The constant is not a leaf.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mffc {
+    pub root: usize,
+    pub nodes: Vec<usize>,
+    pub leaves: Vec<usize>,
+}
+
+impl Mffc {
+    /// number of AndNodes that would be deleted, the root included
+    pub fn size(&self) -> usize {
+        self.nodes.len()
+    }
+}
+
+/// One line of the per-node report, e.g. "node 9: size 3, leaves {1, 2, 4}, nodes {7, 8, 9}"
+impl fmt::Display for Mffc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "node {}: size {}, leaves ", self.root, self.size())?;
+        f.debug_set().entries(&self.leaves).finish()?;
+        write!(f, ", nodes ")?;
+        f.debug_set().entries(&self.nodes).finish()
+    }
+}
+
+/// Computes MFFCs with reference counting (like ABC's Abc_NodeDeref/Abc_NodeRef).
+/// aig: the graph
+/// refs: number of fanouts of every node (AndNodes, latch next states and outputs), see AIG::fanout_counts
+///
+/// To find the MFFC of a node, the node is dereferenced: the reference counts of its fanins are decreased
+/// and every AndNode whose count drops to 0 is dereferenced as well. These are exactly the nodes of the MFFC.
+/// Afterwards the nodes are referenced again, so refs is the same as before and the calculator can be
+/// used for every node of the AIG without counting the fanouts again.
+pub struct MffcCalculator<'a> {
+    pub aig: &'a AIG,
+    refs: HashMap<usize, usize>,
+}
+
+impl<'a> MffcCalculator<'a> {
+    pub fn new(aig: &'a AIG) -> Self {
+        MffcCalculator { aig, refs: aig.fanout_counts() }
+    }
+
+    /// the MFFC of a node, None if the node is not an AndNode
+    pub fn mffc(&mut self, root: usize) -> Option<Mffc> {
+        if !self.aig.node_map.contains_key(&root) {
+            return None;
+        }
+
+        let mut nodes = Vec::new();
+        self.dereference(root, &mut nodes);
+        self.reference(&nodes);
+
+        // the leaves: fanins of the MFFC that are not part of it
+        nodes.sort_unstable();
+        let mut leaves: Vec<usize> = nodes.iter()
+            .flat_map(|node| {
+                let node = &self.aig.node_map[node];
+                [node.left_signal.index(), node.right_signal.index()]
+            })
+            .filter(|fanin| *fanin != 0 && nodes.binary_search(fanin).is_err())
+            .collect();
+        leaves.sort_unstable();
+        leaves.dedup();
+
+        Some(Mffc { root, nodes, leaves })
+    }
+
+    /// the MFFC of every AndNode in increasing order of the nodes
+    pub fn all_mffcs(&mut self) -> Vec<Mffc> {
+        let mut node_ids: Vec<usize> = self.aig.node_map.keys().copied().collect();
+        node_ids.sort_unstable();
+        node_ids.into_iter().filter_map(|node| self.mffc(node)).collect()
+    }
+
+    /// Decrease the reference counts of the fanins of the root, continue with every AndNode whose count
+    /// drops to 0. All dereferenced nodes (the root included) are collected in `nodes`.
+    fn dereference(&mut self, root: usize, nodes: &mut Vec<usize>) {
+        let mut stack = vec![root];
+        while let Some(current) = stack.pop() {
+            nodes.push(current);
+            let node = &self.aig.node_map[&current];
+            for fanin in [node.left_signal.index(), node.right_signal.index()] {
+                if !self.aig.node_map.contains_key(&fanin) {
+                    continue;
+                }
+                let refs = self.refs.get_mut(&fanin).expect("every fanin has a reference");
+                *refs -= 1;
+                if *refs == 0 {
+                    stack.push(fanin);
+                }
+            }
+        }
+    }
+
+    /// undo dereference: increase the reference counts of the fanins of all dereferenced nodes again
+    fn reference(&mut self, nodes: &[usize]) {
+        for current in nodes {
+            let node = &self.aig.node_map[current];
+            for fanin in [node.left_signal.index(), node.right_signal.index()] {
+                if self.aig.node_map.contains_key(&fanin) {
+                    *self.refs.get_mut(&fanin).expect("every fanin has a reference") += 1;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Mffc, MffcCalculator};
+    use crate::aig_structure::aig::AIG;
+
+    #[test]
+    fn shared_fanin_is_a_leaf_of_the_mffc() {
+        // x4 = x1 and x2 is used by x6 and x7, x5 = x2 and x3 only by x6
+        let mut aig = AIG::new();
+        let x1 = aig.add_input(1);
+        let x2 = aig.add_input(2);
+        let x3 = aig.add_input(3);
+        let x4 = aig.create_and(x1, x2, 4);
+        let x5 = aig.create_and(x2, x3, 5);
+        let x6 = aig.create_and(x4, x5, 6);
+        let x7 = aig.create_and(x4, !x3, 7);
+        aig.add_output(x6);
+        aig.add_output(x7);
+
+        let mut calculator = MffcCalculator::new(&aig);
+        let mffc_6 = Mffc { root: 6, nodes: vec![5, 6], leaves: vec![2, 3, 4] };
+        assert_eq!(calculator.mffc(6), Some(mffc_6.clone()));
+        assert_eq!(calculator.mffc(7), Some(Mffc { root: 7, nodes: vec![7], leaves: vec![3, 4] }));
+        assert_eq!(calculator.mffc(4), Some(Mffc { root: 4, nodes: vec![4], leaves: vec![1, 2] }));
+        assert_eq!(calculator.mffc(1), None);
+        // the reference counts are restored after every node
+        assert_eq!(calculator.mffc(6), Some(mffc_6));
+        assert_eq!(calculator.all_mffcs().iter().map(Mffc::size).collect::<Vec<_>>(), vec![1, 1, 2, 1]);
+    }
+}
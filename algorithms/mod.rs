@@ -1 +1,15 @@
+pub mod cut;
+pub mod cut_cost;
 pub mod cut_enumerator;
+pub mod cut_limits;
+pub mod cut_visitor;
+pub mod cut_volume;
+pub mod dominators;
+pub mod exhaustive;
+pub mod mffc;
+pub mod npn;
+pub mod reconvergence_cut;
+pub mod sequential_cut;
+pub mod simulator;
+pub mod ternary;
+pub mod truth_table;
@@ -0,0 +1,286 @@
+use crate::algorithms::cut::Cut;
+use crate::algorithms::truth_table::TruthTable;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Result of the NPN canonization of a function f.
+/// canonical: the representative of the NPN class (the smallest truth table of all NPN transformations of f)
+/// permutation: variable i of the canonical function is variable permutation[i] of f
+/// input_negation: bit j is set if variable j of f is negated
+/// output_negation: true if the output is negated
+///
+/// So canonical(x_0, ..., x_n-1) = output_negation ^ f(y_0, ..., y_n-1) with
+/// y_permutation[i] = x_i ^ (bit permutation[i] of input_negation).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NpnClass {
+    pub canonical: TruthTable,
+    pub permutation: Vec<usize>,
+    pub input_negation: u32,
+    pub output_negation: bool,
+}
+
+/// Exhaustive NPN canonization: tries all n! permutations, all 2^n input negations and both output
+/// polarities and keeps the smallest truth table. The permutations are generated with Heap's algorithm
+/// (one swap of two variables per step) and the input negations in gray code order (one negated variable
+/// per step), so every transformation only costs a few bit operations on one word.
+/// For 6 variables these are 720 * 64 * 2 = 92160 truth tables, which is why the statistics below cache
+/// the result for every function.
+/// Only word-packed truth tables (up to 6 variables) are supported, otherwise None is returned.
+pub fn npn_canonize(truth_table: &TruthTable) -> Option<NpnClass> {
+    let n = truth_table.num_vars();
+    if n > TruthTable::MAX_WORD_VARS {
+        return None;
+    }
+    let num_bits = 1usize << n;
+    let mask = if num_bits == 64 { u64::MAX } else { (1u64 << num_bits) - 1 };
+
+    let mut bits = truth_table.words()[0];
+    let mut perm: Vec<usize> = (0..n).collect();
+    let mut best = NpnClass {
+        canonical: truth_table.clone(),
+        permutation: perm.clone(),
+        input_negation: 0,
+        output_negation: false,
+    };
+    let mut best_bits = u64::MAX;
+
+    // all input negations and output polarities for the current permutation
+    let mut try_phases = |bits: u64, perm: &[usize], best: &mut NpnClass| {
+        let mut current = bits;
+        let mut negation = 0u32; // in terms of the variables of f
+        for step in 0..1usize << n {
+            if step > 0 {
+                // gray code: the variable that changes is the lowest set bit of step
+                let var = step.trailing_zeros() as usize;
+                current = TruthTable::flip_var(current, var);
+                negation ^= 1 << perm[var];
+            }
+            for output_negation in [false, true] {
+                let candidate = if output_negation { !current & mask } else { current & mask };
+                if candidate < best_bits {
+                    best_bits = candidate;
+                    best.permutation = perm.to_vec();
+                    best.input_negation = negation;
+                    best.output_negation = output_negation;
+                }
+            }
+        }
+    };
+
+    // Heap's algorithm (iterative version)
+    try_phases(bits, &perm, &mut best);
+    let mut c = vec![0usize; n];
+    let mut i = 1;
+    while i < n {
+        if c[i] < i {
+            let j = if i % 2 == 0 { 0 } else { c[i] };
+            bits = TruthTable::swap_vars(bits, j, i);
+            perm.swap(j, i);
+            try_phases(bits, &perm, &mut best);
+            c[i] += 1;
+            i = 1;
+        } else {
+            c[i] = 0;
+            i += 1;
+        }
+    }
+
+    // repeat the pattern over the whole word like every word-packed truth table
+    let mut canonical = best_bits;
+    let mut width = num_bits;
+    while width < 64 {
+        canonical |= canonical << width;
+        width *= 2;
+    }
+    best.canonical = TruthTable::Word { num_vars: n as u8, bits: canonical };
+    Some(best)
+}
+
+/// How often every NPN class occurs in the cuts of a design (like ABC's `&cut -n`).
+/// classes: number of cuts per canonical truth table
+/// total_cuts: number of cuts that were canonized
+/// unsupported_cuts: cuts with more than 6 leaves (they are not canonized)
+/// cache: canonical form of every function that was seen so far, most cuts share a few functions
+#[derive(Debug, Default)]
+pub struct NpnStatistics {
+    pub classes: HashMap<TruthTable, usize>,
+    pub total_cuts: usize,
+    pub unsupported_cuts: usize,
+    cache: HashMap<TruthTable, TruthTable>,
+}
+
+impl NpnStatistics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Collect the statistics for all cuts of all nodes. Trivial cuts (the node itself) and cuts without
+    /// a truth table are skipped, so CutEnumerator::compute_truth_tables has to be set.
+    pub fn from_cuts(cuts: &HashMap<usize, Vec<Cut>>) -> Self {
+        let mut statistics = Self::new();
+        for (&node, node_cuts) in cuts {
+            statistics.add_node_cuts(node, node_cuts);
+        }
+        statistics
+    }
+
+    /// count the cuts of a single node without its trivial cut, e.g. from CutEnumerator::visit_cuts
+    pub fn add_node_cuts(&mut self, node: usize, cuts: &[Cut]) {
+        for cut in cuts {
+            let is_trivial = cut.len() == 1 && cut.contains(node);
+            if !is_trivial {
+                self.add_cut(cut);
+            }
+        }
+    }
+
+    /// count the NPN class of a single cut
+    pub fn add_cut(&mut self, cut: &Cut) {
+        let Some(truth_table) = &cut.truth_table else {
+            return;
+        };
+        if let Some(canonical) = self.canonical(truth_table) {
+            *self.classes.entry(canonical).or_insert(0) += 1;
+            self.total_cuts += 1;
+        } else {
+            self.unsupported_cuts += 1;
+        }
+    }
+
+    /// the canonical form of a function (cached)
+    pub fn canonical(&mut self, truth_table: &TruthTable) -> Option<TruthTable> {
+        if let Some(canonical) = self.cache.get(truth_table) {
+            return Some(canonical.clone());
+        }
+        let canonical = npn_canonize(truth_table)?.canonical;
+        self.cache.insert(truth_table.clone(), canonical.clone());
+        Some(canonical)
+    }
+
+    /// the classes ordered by their number of cuts (most frequent first), ties by the number of variables and the truth table
+    pub fn sorted_classes(&self) -> Vec<(&TruthTable, usize)> {
+        let mut classes: Vec<(&TruthTable, usize)> = self.classes.iter().map(|(tt, &count)| (tt, count)).collect();
+        classes.sort_by(|a, b| {
+            b.1.cmp(&a.1)
+                .then(a.0.num_vars().cmp(&b.0.num_vars()))
+                .then(a.0.words().cmp(b.0.words()))
+        });
+        classes
+    }
+}
+
+/// The report: one line per NPN class with its truth table, number of variables, number of cuts and share of all cuts.
+impl fmt::Display for NpnStatistics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "NPN classes: {} (cuts: {}, cuts with more than 6 leaves: {})", self.classes.len(), self.total_cuts, self.unsupported_cuts)?;
+        writeln!(f, "{:>5}  {:<20} {:>4} {:>10} {:>8}", "class", "truth table", "vars", "cuts", "percent")?;
+        for (i, (truth_table, count)) in self.sorted_classes().into_iter().enumerate() {
+            let percent = 100.0 * count as f64 / self.total_cuts.max(1) as f64;
+            writeln!(f, "{:>5}  {:<20} {:>4} {:>10} {:>7.2}%", i, truth_table.to_string(), truth_table.num_vars(), count, percent)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::npn_canonize;
+    use crate::algorithms::truth_table::TruthTable;
+
+    fn from_fn(num_vars: usize, f: impl Fn(usize) -> bool) -> TruthTable {
+        let binary: String = (0..1usize << num_vars).rev().map(|m| if f(m) { '1' } else { '0' }).collect();
+        TruthTable::from_binary(num_vars, &binary).unwrap()
+    }
+
+    /// the transformation of NpnClass: output_negation ^ f(y) with y_permutation[i] = x_i ^ negation bit permutation[i]
+    fn transform(f: &TruthTable, permutation: &[usize], input_negation: u32, output_negation: bool) -> TruthTable {
+        from_fn(f.num_vars(), |x| {
+            let y = permutation.iter().enumerate()
+                .map(|(i, &p)| (((x >> i) & 1) ^ ((input_negation as usize >> p) & 1)) << p)
+                .sum();
+            f.bit(y) ^ output_negation
+        })
+    }
+
+    fn permutations(n: usize) -> Vec<Vec<usize>> {
+        if n == 0 {
+            return vec![Vec::new()];
+        }
+        let mut result = Vec::new();
+        for smaller in permutations(n - 1) {
+            for position in 0..n {
+                let mut permutation = smaller.clone();
+                permutation.insert(position, n - 1);
+                result.push(permutation);
+            }
+        }
+        result
+    }
+
+    /// the smallest truth table of all transformations, one transformation after the other
+    fn brute_force(f: &TruthTable) -> TruthTable {
+        let n = f.num_vars();
+        let mut best: Option<TruthTable> = None;
+        for permutation in permutations(n) {
+            for input_negation in 0..1u32 << n {
+                for output_negation in [false, true] {
+                    let candidate = transform(f, &permutation, input_negation, output_negation);
+                    if best.as_ref().is_none_or(|best| candidate.words()[0] < best.words()[0]) {
+                        best = Some(candidate);
+                    }
+                }
+            }
+        }
+        best.unwrap()
+    }
+
+    fn examples() -> Vec<(&'static str, TruthTable)> {
+        let ones = |m: usize| m.count_ones();
+        vec![
+            ("and2", from_fn(2, |m| m == 3)),
+            ("maj3", from_fn(3, |m| ones(m) >= 2)),
+            ("xor3", from_fn(3, |m| ones(m) % 2 == 1)),
+            ("random4", TruthTable::from_hex(4, "b4e1").unwrap()),
+            ("random5", TruthTable::from_hex(5, "8f3a6c15").unwrap()),
+        ]
+    }
+
+    #[test]
+    fn canonical_form_matches_brute_force() {
+        for (name, f) in examples() {
+            let class = npn_canonize(&f).unwrap();
+            assert_eq!(class.canonical, brute_force(&f), "{name}");
+            // the transformation of the result really gives the canonical form
+            let transformed = transform(&f, &class.permutation, class.input_negation, class.output_negation);
+            assert_eq!(transformed, class.canonical, "{name}");
+        }
+    }
+
+    #[test]
+    fn all_npn_variants_have_the_same_class() {
+        for (name, f) in examples() {
+            let n = f.num_vars();
+            let canonical = npn_canonize(&f).unwrap().canonical;
+            for (k, permutation) in permutations(n).into_iter().enumerate() {
+                // not all 2^n negations for every permutation, but every negation for some permutation
+                let input_negation = (k as u32).wrapping_mul(2654435761) % (1 << n);
+                for output_negation in [false, true] {
+                    let variant = transform(&f, &permutation, input_negation, output_negation);
+                    let class = npn_canonize(&variant).unwrap();
+                    assert_eq!(class.canonical, canonical, "{name}, variant {variant}");
+                    assert_eq!(transform(&variant, &class.permutation, class.input_negation, class.output_negation), canonical);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn known_classes() {
+        // and2 with a negated output and inputs is or2 -> the smallest member is the nor of both inputs (0x1)
+        assert_eq!(npn_canonize(&from_fn(2, |m| m != 0)).unwrap().canonical.to_hex(), "1");
+        // xor3 and xnor3 are one class
+        let xnor3 = from_fn(3, |m| m.count_ones() % 2 == 0);
+        assert_eq!(npn_canonize(&xnor3).unwrap().canonical.to_hex(), "69");
+        assert!(npn_canonize(&TruthTable::constant(7, false)).is_none());
+    }
+}
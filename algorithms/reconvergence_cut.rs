@@ -0,0 +1,144 @@
+use crate::aig_structure::aig::AIG;
+use std::collections::HashSet;
+
+/// A single large cut of a node, computed by AIG::reconvergence_cut.
+/// root: the node of the cut
+/// leaves: the leaves in increasing order
+/// internal: all nodes between the leaves and the root in topological order, the root is the last one
+///           (the leaves are not included)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReconvergenceCut {
+    pub root: usize,
+    pub leaves: Vec<usize>,
+    pub internal: Vec<usize>,
+}
+
+impl AIG {
+    /// Reconvergence-driven cut (like Abc_NodeFindCut in ABC): instead of enumerating all cuts, one cut
+    /// with at most max_leaves leaves is grown from the root. It starts with the fanins of the root. In
+    /// every step the leaf that adds the fewest new leaves is replaced by its fanins. Fanins that are
+    /// already in the cone cost nothing, so leaves where paths reconverge are preferred and the cut
+    /// contains as much logic as possible. The expansion stops if every leaf is an input (or latch) or
+    /// if every expansion would exceed max_leaves.
+    /// Because nothing is enumerated, this also works for 10 to 16 leaves (or more) where the full
+    /// enumeration of CutEnumerator::calculate_cuts_single_node is not feasible.
+    ///
+    /// Returns None if target_node is not part of the AIG. For an input or a latch the cut is the node
+    /// itself without internal nodes. The fanins of the root are always returned, even if max_leaves < 2.
+    /// Ties are broken by the larger index, so the same cut is computed on every run.
+    pub fn reconvergence_cut(&self, target_node: usize, max_leaves: usize) -> Option<ReconvergenceCut> {
+        let is_input = self.inputs.iter().any(|sig| sig.index() == target_node);
+        let is_latch = self.latches.iter().any(|latch| latch.index == target_node);
+        let Some(root) = self.node_map.get(&target_node) else {
+            if is_input || is_latch {
+                return Some(ReconvergenceCut { root: target_node, leaves: vec![target_node], internal: Vec::new() });
+            }
+            return None;
+        };
+
+        // visited: all nodes of the cone (root, internal nodes and leaves). The constant is never a leaf.
+        let mut visited: HashSet<usize> = HashSet::from([target_node, 0]);
+        let mut leaves: Vec<usize> = Vec::new();
+        for fanin in [root.left_signal.index(), root.right_signal.index()] {
+            if visited.insert(fanin) {
+                leaves.push(fanin);
+            }
+        }
+
+        loop {
+            // the cost of a leaf: number of its fanins that are not in the cone yet
+            // (inputs and latches can not be expanded)
+            let best = leaves.iter().enumerate()
+                .filter_map(|(pos, &leaf)| {
+                    let node = self.node_map.get(&leaf)?;
+                    let cost = [node.left_signal.index(), node.right_signal.index()].iter()
+                        .filter(|fanin| !visited.contains(fanin))
+                        .count();
+                    Some((cost, leaf, pos))
+                })
+                .min_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)));
+
+            // the leaf itself is removed, so the cut grows by cost - 1 leaves
+            let Some((cost, leaf, pos)) = best else {
+                break;
+            };
+            if leaves.len() + cost - 1 > max_leaves {
+                break;
+            }
+
+            leaves.swap_remove(pos);
+            let node = &self.node_map[&leaf];
+            for fanin in [node.left_signal.index(), node.right_signal.index()] {
+                if visited.insert(fanin) {
+                    leaves.push(fanin);
+                }
+            }
+        }
+        leaves.sort_unstable();
+
+        let internal = self.cone_in_topological_order(target_node, &leaves);
+        Some(ReconvergenceCut { root: target_node, leaves, internal })
+    }
+
+    /// All AndNodes between the leaves and the root in topological order (iterative post-order DFS
+    /// that stops at the leaves).
+    fn cone_in_topological_order(&self, root: usize, leaves: &[usize]) -> Vec<usize> {
+        let mut order = Vec::new();
+        let mut visited: HashSet<usize> = leaves.iter().copied().collect();
+        visited.insert(0);
+        // (node, fanins already pushed)
+        let mut stack = vec![(root, false)];
+        while let Some((current, expanded)) = stack.pop() {
+            if expanded {
+                order.push(current);
+                continue;
+            }
+            if !visited.insert(current) {
+                continue;
+            }
+            let node = &self.node_map[&current];
+            stack.push((current, true));
+            stack.push((node.right_signal.index(), false));
+            stack.push((node.left_signal.index(), false));
+        }
+        order
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ReconvergenceCut;
+    use crate::aig_structure::aig::AIG;
+
+    /// x9 = (x5 and x6) and x8 with x5 = x1 and x2, x6 = x2 and x3, x8 = x3 and x4:
+    /// the paths reconverge at x2 and x3
+    fn example() -> AIG {
+        let mut aig = AIG::new();
+        let x: Vec<_> = (1..=4).map(|index| aig.add_input(index)).collect();
+        let x5 = aig.create_and(x[0], x[1], 5);
+        let x6 = aig.create_and(x[1], x[2], 6);
+        let x7 = aig.create_and(x5, x6, 7);
+        let x8 = aig.create_and(x[2], x[3], 8);
+        let x9 = aig.create_and(x7, x8, 9);
+        aig.add_output(x9);
+        aig
+    }
+
+    #[test]
+    fn cut_respects_the_leaf_limit() {
+        let aig = example();
+        // {7, 8} -> 8 is expanded (tie, larger index) -> {3, 4, 7}, expanding 7 would give 4 leaves
+        let cut = aig.reconvergence_cut(9, 3).unwrap();
+        assert_eq!(cut, ReconvergenceCut { root: 9, leaves: vec![3, 4, 7], internal: vec![8, 9] });
+
+        // with 4 leaves the whole cone fits: after 7, the leaves 6 and 5 only add one leaf each
+        let cut = aig.reconvergence_cut(9, 4).unwrap();
+        assert_eq!(cut, ReconvergenceCut { root: 9, leaves: vec![1, 2, 3, 4], internal: vec![5, 6, 7, 8, 9] });
+
+        for max_leaves in 2..=4 {
+            assert!(aig.reconvergence_cut(9, max_leaves).unwrap().leaves.len() <= max_leaves);
+        }
+        assert_eq!(aig.reconvergence_cut(2, 3).unwrap().leaves, vec![2]);
+        assert_eq!(aig.reconvergence_cut(42, 3), None);
+    }
+}
@@ -0,0 +1,243 @@
+use crate::algorithms::cut_enumerator::CutEnumerator;
+use std::collections::HashMap;
+use std::fmt;
+
+/// A leaf of a sequential cut: a node together with the number of latches (registers) between the
+/// leaf and the root of the cut. A node can be a leaf with several latencies.
+/// node: index of the leaf
+/// latency: number of latches on the paths from the leaf to the root, so the root depends on the
+///          value of the leaf latency clock cycles ago
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SeqLeaf {
+    pub node: usize,
+    pub latency: usize,
+}
+
+/// A sequential cut: a set of leaves with latencies, sorted by node and latency.
+/// In contrast to the combinational cuts, the cut enumeration does not stop at latches. A latch is
+/// replaced by the cuts of its next state signal, with one more register on every leaf. The empty cut
+/// is the cut of the constant.
+#[derive(Clone, PartialEq, Eq, Hash, Default)]
+pub struct SequentialCut {
+    pub leaves: Vec<SeqLeaf>,
+}
+
+impl SequentialCut {
+    /// the trivial cut {node@0}
+    pub fn trivial(node: usize) -> Self {
+        SequentialCut { leaves: vec![SeqLeaf { node, latency: 0 }] }
+    }
+
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    /// the largest latency of all leaves (0 for the empty cut)
+    pub fn max_latency(&self) -> usize {
+        self.leaves.iter().map(|leaf| leaf.latency).max().unwrap_or(0)
+    }
+
+    /// union of two cuts, None if it has more than cut_size leaves
+    pub fn merge(&self, other: &SequentialCut, cut_size: usize) -> Option<SequentialCut> {
+        let mut leaves = Vec::with_capacity(self.len() + other.len());
+        let (mut i, mut j) = (0, 0);
+        while i < self.len() || j < other.len() {
+            let leaf = if j == other.len() || (i < self.len() && self.leaves[i] < other.leaves[j]) {
+                i += 1;
+                self.leaves[i - 1]
+            } else if i == self.len() || other.leaves[j] < self.leaves[i] {
+                j += 1;
+                other.leaves[j - 1]
+            } else {
+                i += 1;
+                j += 1;
+                self.leaves[i - 1]
+            };
+            if leaves.len() == cut_size {
+                return None;
+            }
+            leaves.push(leaf);
+        }
+        Some(SequentialCut { leaves })
+    }
+
+    /// the same cut seen through one more latch: every latency is increased by one
+    pub fn delayed(&self) -> SequentialCut {
+        SequentialCut {
+            leaves: self.leaves.iter().map(|leaf| SeqLeaf { node: leaf.node, latency: leaf.latency + 1 }).collect(),
+        }
+    }
+
+    /// true if self is a subset of other. Equal cuts dominate each other.
+    pub fn dominates(&self, other: &SequentialCut) -> bool {
+        if self.len() > other.len() {
+            return false;
+        }
+        let mut other_leaves = other.leaves.iter();
+        self.leaves.iter().all(|leaf| other_leaves.any(|l| l == leaf))
+    }
+}
+
+/// Print a sequential cut like a set of node@latency, e.g. {3@0, 5@1}
+impl fmt::Debug for SequentialCut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        for (i, leaf) in self.leaves.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}@{}", leaf.node, leaf.latency)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+/// Result of CutEnumerator::enumerate_sequential_cuts
+/// cuts: the sequential cuts of every input, latch and AndNode in the transitive fanin of the outputs and latches
+/// iterations: number of iterations that were needed
+/// converged: false if the cuts of the latches still changed in the last iteration (iteration limit reached)
+#[derive(Debug, Default)]
+pub struct SequentialCuts {
+    pub cuts: HashMap<usize, Vec<SequentialCut>>,
+    pub iterations: usize,
+    pub converged: bool,
+}
+
+impl<'a> CutEnumerator<'a> {
+    /// Sequential cut enumeration (as in SeqMapII): the cuts may cross latches, every leaf carries the
+    /// number of latches between the leaf and the root. This is the basis for retiming-aware mapping.
+    /// cut_size: maximum number of leaves of a cut
+    /// max_latency: cuts with a leaf behind more latches are dropped
+    /// max_iterations: bound for the fixpoint iteration
+    ///
+    /// A latch has the trivial cut and the cuts of its next state signal with one more latch on every
+    /// leaf. With feedback loops through latches, the cuts of a latch depend on themselves, so the
+    /// cuts are computed iteratively: every iteration processes the nodes in topological order (the latches
+    /// are sources there) with the latch cuts of the last iteration and then updates the latch cuts. The
+    /// iteration stops when the latch cuts do not change anymore. Without a cut limit this happens after
+    /// at most max_latency + 2 iterations, because every iteration can only add one more latch to a leaf.
+    /// The cut_limit and sort order of the enumerator are used for the AndNodes (fewest leaves first,
+    /// ties by smallest latency).
+    pub fn enumerate_sequential_cuts(&self, cut_size: usize, max_latency: usize, max_iterations: usize) -> SequentialCuts {
+        assert!(self.cut_limit != Some(0), "cut_limit has to be at least 1 (the trivial cut is always kept)");
+        let roots: Vec<usize> = self.aig.outputs.iter()
+            .chain(self.aig.latches.iter().map(|latch| &latch.next))
+            .map(|signal| signal.index())
+            .filter(|&root| root != 0)
+            .collect();
+        let topo_order = self.aig.topological_sort_from(&roots);
+
+        let mut result = SequentialCuts::default();
+        // the constant has the empty cut, inputs and latches start with their trivial cut
+        result.cuts.insert(0, vec![SequentialCut::default()]);
+        for input in &self.aig.inputs {
+            result.cuts.insert(input.index(), vec![SequentialCut::trivial(input.index())]);
+        }
+        for latch in &self.aig.latches {
+            result.cuts.insert(latch.index, vec![SequentialCut::trivial(latch.index)]);
+        }
+
+        while result.iterations < max_iterations {
+            result.iterations += 1;
+
+            for &node_idx in &topo_order {
+                let Some(node) = self.aig.node_map.get(&node_idx) else {
+                    continue;
+                };
+                let mut new_cuts = Vec::new();
+                for cut_l in &result.cuts[&node.left_signal.index()] {
+                    for cut_r in &result.cuts[&node.right_signal.index()] {
+                        if let Some(union) = cut_l.merge(cut_r, cut_size) {
+                            new_cuts.push(union);
+                        }
+                    }
+                }
+                let cuts = self.select_sequential_cuts(node_idx, new_cuts);
+                result.cuts.insert(node_idx, cuts);
+            }
+
+            // the latch cuts of the next iteration, computed from the cuts of this iteration
+            let mut changed = false;
+            for latch in &self.aig.latches {
+                let next_cuts = result.cuts.get(&latch.next.index()).cloned().unwrap_or_default();
+                let new_cuts: Vec<SequentialCut> = next_cuts.iter()
+                    .map(SequentialCut::delayed)
+                    .filter(|cut| cut.max_latency() <= max_latency)
+                    .collect();
+                let cuts = self.select_sequential_cuts(latch.index, new_cuts);
+                if result.cuts[&latch.index] != cuts {
+                    result.cuts.insert(latch.index, cuts);
+                    changed = true;
+                }
+            }
+            if !changed {
+                result.converged = true;
+                break;
+            }
+        }
+        result
+    }
+
+    /// Remove dominated cuts, keep the best cut_limit - 1 of them and add the trivial cut.
+    fn select_sequential_cuts(&self, node_idx: usize, cuts: Vec<SequentialCut>) -> Vec<SequentialCut> {
+        let trivial = SequentialCut::trivial(node_idx);
+        let mut minimal: Vec<SequentialCut> = Vec::new();
+        for cut in cuts {
+            // the trivial cut is added at the end, a cut of a latch can contain the latch itself again
+            if cut == trivial || minimal.iter().any(|c| c.dominates(&cut)) {
+                continue;
+            }
+            minimal.retain(|c| !cut.dominates(c));
+            minimal.push(cut);
+        }
+        if let Some(cut_limit) = self.cut_limit {
+            minimal.sort_by_key(|cut| (cut.len(), cut.max_latency()));
+            minimal.truncate(cut_limit - 1);
+        }
+        minimal.push(trivial);
+        minimal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SeqLeaf, SequentialCut};
+    use crate::aig_structure::aig::AIG;
+    use crate::algorithms::cut_enumerator::CutEnumerator;
+
+    fn cut(leaves: &[(usize, usize)]) -> SequentialCut {
+        SequentialCut { leaves: leaves.iter().map(|&(node, latency)| SeqLeaf { node, latency }).collect() }
+    }
+
+    #[test]
+    fn cut_crosses_one_latch() {
+        // latch x3 stores x4 = x1 and x2, the output is x5 = x3 and x1
+        let mut aig = AIG::new();
+        let x1 = aig.add_input(1);
+        let x2 = aig.add_input(2);
+        let x3 = aig.add_latch(3, Some(false));
+        let x4 = aig.create_and(x1, x2, 4);
+        let x5 = aig.create_and(x1, x3, 5);
+        aig.set_latch_next(0, x4);
+        aig.add_output(x5);
+        let cut_enumerator = CutEnumerator::new(&aig);
+
+        let result = cut_enumerator.enumerate_sequential_cuts(4, 1, 10);
+        assert!(result.converged);
+        assert_eq!(result.cuts[&3], vec![cut(&[(1, 1), (2, 1)]), cut(&[(4, 1)]), cut(&[(3, 0)])]);
+        assert_eq!(result.cuts[&5], vec![
+            cut(&[(1, 0), (1, 1), (2, 1)]),
+            cut(&[(1, 0), (4, 1)]),
+            cut(&[(1, 0), (3, 0)]),
+            cut(&[(5, 0)]),
+        ]);
+
+        // without latency the cuts stop at the latch
+        let result = cut_enumerator.enumerate_sequential_cuts(4, 0, 10);
+        assert_eq!(result.cuts[&5], vec![cut(&[(1, 0), (3, 0)]), cut(&[(5, 0)])]);
+    }
+}
@@ -0,0 +1,301 @@
+use crate::aig_structure::aig::AIG;
+use crate::aig_structure::signal::Signal;
+use crate::error::{CrustError, Result};
+use std::collections::HashMap;
+
+/// Small pseudo random number generator (xorshift64*), so the random patterns are the same on every
+/// run and platform for the same seed.
+pub struct XorShift64 {
+    state: u64,
+}
+
+impl XorShift64 {
+    /// a seed of 0 is replaced, because xorshift would only produce zeros
+    pub fn new(seed: u64) -> Self {
+        XorShift64 { state: if seed == 0 { 0x9E37_79B9_7F4A_7C15 } else { seed } }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+}
+
+/// Values of a cycle by cycle simulation, one entry per cycle. The values are bools for the binary simulation
+/// and Ternary values for the ternary simulation.
+/// inputs: the input values of every cycle in AIGER order
+/// latches: the state of the latches at the beginning of every cycle
+/// outputs: the output values of every cycle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trace<T = bool> {
+    pub inputs: Vec<Vec<T>>,
+    pub latches: Vec<Vec<T>>,
+    pub outputs: Vec<Vec<T>>,
+}
+
+impl<T> Default for Trace<T> {
+    fn default() -> Self {
+        Trace { inputs: Vec::new(), latches: Vec::new(), outputs: Vec::new() }
+    }
+}
+
+impl<T> Trace<T> {
+    pub fn num_cycles(&self) -> usize {
+        self.inputs.len()
+    }
+}
+
+/// Bit-parallel simulation of the combinational part of an AIG. Every node gets a signature of num_words
+/// 64-bit words, bit i of the signature is the value of the node for pattern i. The AndNodes are computed
+/// word by word in topological order, so 64 patterns are simulated with one and-operation.
+/// aig: the graph
+/// num_words: number of words per signature (64 * num_words patterns)
+/// patterns: values of the inputs and latch outputs (latches are treated as free inputs). Missing
+///           patterns are 0.
+/// signatures: values of all nodes after run, the constant included (always 0)
+/// order: topological order of all AndNodes
+///
+/// The AIG has to be consistent (see `check`), otherwise run panics.
+pub struct Simulator<'a> {
+    pub aig: &'a AIG,
+    pub num_words: usize,
+    pub patterns: HashMap<usize, Vec<u64>>,
+    pub signatures: HashMap<usize, Vec<u64>>,
+    order: Vec<usize>,
+}
+
+impl<'a> Simulator<'a> {
+    pub fn new(aig: &'a AIG, num_words: usize) -> Self {
+        assert!(num_words > 0, "a signature needs at least one word");
+        let order = aig.topological_sort().into_iter().filter(|node| aig.node_map.contains_key(node)).collect();
+        Simulator { aig, num_words, patterns: HashMap::new(), signatures: HashMap::new(), order }
+    }
+
+    /// the inputs and latch outputs in AIGER order
+    fn sources(&self) -> Vec<usize> {
+        self.aig.inputs.iter().map(|input| input.index())
+            .chain(self.aig.latches.iter().map(|latch| latch.index))
+            .collect()
+    }
+
+    /// Set the pattern of an input or latch output. Shorter patterns are filled up with 0, longer ones are cut.
+    pub fn set_pattern(&mut self, node: usize, mut words: Vec<u64>) {
+        words.resize(self.num_words, 0);
+        self.patterns.insert(node, words);
+    }
+
+    /// random patterns for all inputs and latch outputs
+    pub fn randomize(&mut self, seed: u64) {
+        let mut rng = XorShift64::new(seed);
+        for node in self.sources() {
+            let words = (0..self.num_words).map(|_| rng.next_u64()).collect();
+            self.patterns.insert(node, words);
+        }
+    }
+
+    /// propagate the patterns through all AndNodes
+    pub fn run(&mut self) {
+        self.signatures.clear();
+        self.signatures.insert(0, vec![0; self.num_words]);
+        for node in self.sources() {
+            let words = self.patterns.get(&node).cloned().unwrap_or_else(|| vec![0; self.num_words]);
+            self.signatures.insert(node, words);
+        }
+
+        for &node_idx in &self.order {
+            let node = &self.aig.node_map[&node_idx];
+            let left = self.signal_words(node.left_signal);
+            let right = self.signal_words(node.right_signal);
+            let words = left.zip(right).map(|(l, r)| l & r).collect();
+            self.signatures.insert(node_idx, words);
+        }
+    }
+
+    /// the words of a signal, inverted if the signal is inverted
+    fn signal_words(&self, signal: Signal) -> impl Iterator<Item = u64> + '_ {
+        let mask = if signal.is_complemented() { u64::MAX } else { 0 };
+        self.signatures.get(&signal.index())
+            .unwrap_or_else(|| panic!("node {} is neither an input, a latch nor an AndNode", signal.index()))
+            .iter()
+            .map(move |word| word ^ mask)
+    }
+
+    /// the signature of a node after run, None if it was not simulated
+    pub fn signature(&self, node: usize) -> Option<&[u64]> {
+        self.signatures.get(&node).map(|words| words.as_slice())
+    }
+
+    /// the value of a signal for all patterns (the complement of the node signature if the signal is inverted)
+    pub fn signal_value(&self, signal: Signal) -> Vec<u64> {
+        self.signal_words(signal).collect()
+    }
+
+    /// the value of a signal for a single pattern
+    pub fn signal_bit(&self, signal: Signal, pattern: usize) -> bool {
+        let word = self.signatures[&signal.index()][pattern / 64];
+        ((word >> (pattern % 64)) & 1 == 1) ^ signal.is_complemented()
+    }
+
+    /// the values of all outputs
+    pub fn output_values(&self) -> Vec<Vec<u64>> {
+        self.aig.outputs.iter().map(|&output| self.signal_value(output)).collect()
+    }
+
+    /// Simulate one input vector per cycle, starting with the given latch state. In every cycle the outputs
+    /// are computed from the inputs and the current state, then the latches take the values of their next
+    /// state signals. Only the first pattern of the signatures is used.
+    /// The initial state needs one value per latch and every vector one value per input, otherwise
+    /// InvalidArgument is returned.
+    pub fn simulate_cycles(&mut self, initial_state: &[bool], vectors: &[Vec<bool>]) -> Result<Trace> {
+        if initial_state.len() != self.aig.latches.len() {
+            return Err(CrustError::InvalidArgument(format!(
+                "the initial state has {} values, but the AIG has {} latches", initial_state.len(), self.aig.latches.len()
+            )));
+        }
+        if let Some(cycle) = vectors.iter().position(|vector| vector.len() != self.aig.inputs.len()) {
+            return Err(CrustError::InvalidArgument(format!(
+                "the input vector of cycle {cycle} has {} values, but the AIG has {} inputs", vectors[cycle].len(), self.aig.inputs.len()
+            )));
+        }
+        let num_words = self.num_words;
+        let to_words = |value: bool| vec![if value { u64::MAX } else { 0 }; num_words];
+        let mut trace = Trace::default();
+        let mut state = initial_state.to_vec();
+        for vector in vectors {
+            for (input, &value) in self.aig.inputs.iter().zip(vector) {
+                self.patterns.insert(input.index(), to_words(value));
+            }
+            for (latch, &value) in self.aig.latches.iter().zip(&state) {
+                self.patterns.insert(latch.index, to_words(value));
+            }
+            self.run();
+
+            let outputs = self.aig.outputs.iter().map(|&output| self.signal_bit(output, 0)).collect();
+            let next_state = self.aig.latches.iter().map(|latch| self.signal_bit(latch.next, 0)).collect();
+            trace.inputs.push(vector.clone());
+            trace.latches.push(std::mem::replace(&mut state, next_state));
+            trace.outputs.push(outputs);
+        }
+        Ok(trace)
+    }
+
+    /// Equivalence candidates: AndNodes with the same signature or the complemented signature. Every class
+    /// has at least two members, the first member is the node with the smallest index and the phase of the
+    /// other members is relative to it. The classes are sorted by their first member. Nodes that are equal
+    /// for all simulated patterns are only candidates, they still have to be proven equal.
+    pub fn equivalence_candidates(&self) -> Vec<Vec<Signal>> {
+        let mut node_ids: Vec<usize> = self.order.clone();
+        node_ids.sort_unstable();
+
+        // normalize the phase: the signature with bit 0 = 0
+        let mut classes: HashMap<Vec<u64>, Vec<Signal>> = HashMap::new();
+        for node in node_ids {
+            let words = &self.signatures[&node];
+            let inverted = words[0] & 1 == 1;
+            let key = if inverted { words.iter().map(|word| !word).collect() } else { words.clone() };
+            classes.entry(key).or_default().push(Signal::new(node, inverted));
+        }
+
+        let mut result: Vec<Vec<Signal>> = classes.into_values()
+            .filter(|class| class.len() > 1)
+            .map(|class| {
+                let phase = class[0].is_complemented();
+                class.into_iter().map(|signal| Signal::new(signal.index(), signal.is_complemented() ^ phase)).collect()
+            })
+            .collect();
+        result.sort_by_key(|class| class[0].index());
+        result
+    }
+}
+
+/// hexadecimal representation of a signature, the highest pattern first (like TruthTable::to_hex)
+pub fn signature_to_hex(words: &[u64]) -> String {
+    words.iter().rev().map(|word| format!("{word:016x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Simulator;
+    use crate::aig_structure::aig::AIG;
+    use crate::aig_structure::signal::Signal;
+    use crate::error::CrustError;
+
+    /// a XOR b with two AndNodes and an inverted output: !(!(a and !b) and !(!a and b))
+    fn xor(aig: &mut AIG, a: Signal, b: Signal, index: usize) -> Signal {
+        let left = aig.create_and(a, !b, index);
+        let right = aig.create_and(!a, b, index + 1);
+        !aig.create_and(!left, !right, index + 2)
+    }
+
+    /// 2-bit counter with an enable input x1: bit0' = bit0 XOR en, bit1' = bit1 XOR (bit0 and en).
+    /// The outputs are bit0 and bit1.
+    fn counter() -> AIG {
+        let mut aig = AIG::new();
+        let enable = aig.add_input(1);
+        let bit0 = aig.add_latch(2, Some(false));
+        let bit1 = aig.add_latch(3, Some(false));
+        let next0 = xor(&mut aig, bit0, enable, 4);
+        let carry = aig.create_and(bit0, enable, 7);
+        let next1 = xor(&mut aig, bit1, carry, 8);
+        aig.set_latch_next(0, next0);
+        aig.set_latch_next(1, next1);
+        aig.add_output(bit0);
+        aig.add_output(bit1);
+        aig
+    }
+
+    #[test]
+    fn counter_counts_the_enabled_cycles() {
+        let aig = counter();
+        let enable = [true, true, false, true, true, true, false];
+        let vectors: Vec<Vec<bool>> = enable.iter().map(|&value| vec![value]).collect();
+        let trace = Simulator::new(&aig, 1).simulate_cycles(&[false, false], &vectors).unwrap();
+
+        let count = |bits: &Vec<bool>| usize::from(bits[0]) + 2 * usize::from(bits[1]);
+        assert_eq!(trace.num_cycles(), 7);
+        assert_eq!(trace.latches.iter().map(count).collect::<Vec<_>>(), vec![0, 1, 2, 2, 3, 0, 1]);
+        assert_eq!(trace.outputs, trace.latches);
+        assert_eq!(trace.inputs, vectors);
+
+        // starting at 3, one enabled cycle wraps around
+        let trace = Simulator::new(&aig, 1).simulate_cycles(&[true, true], &[vec![true], vec![false]]).unwrap();
+        assert_eq!(trace.latches.iter().map(count).collect::<Vec<_>>(), vec![3, 0]);
+    }
+
+    #[test]
+    fn cycles_need_one_value_per_input_and_latch() {
+        let aig = counter();
+        let mut simulator = Simulator::new(&aig, 1);
+        assert!(matches!(simulator.simulate_cycles(&[false], &[vec![true]]), Err(CrustError::InvalidArgument(_))));
+        let vectors = vec![vec![true], vec![true, false]];
+        assert!(matches!(simulator.simulate_cycles(&[false, false], &vectors), Err(CrustError::InvalidArgument(_))));
+        assert_eq!(simulator.simulate_cycles(&[false, false], &[]).unwrap().num_cycles(), 0);
+    }
+
+    #[test]
+    fn equivalent_nodes_are_candidates() {
+        // x5 = (x1 and x2) and x3 and x7 = x1 and (x2 and x3) are equal, x8 = !x5 and x1 is different from all nodes
+        let mut aig = AIG::new();
+        let x: Vec<Signal> = (1..=3).map(|index| aig.add_input(index)).collect();
+        let x4 = aig.create_and(x[0], x[1], 4);
+        let x5 = aig.create_and(x4, x[2], 5);
+        let x6 = aig.create_and(x[1], x[2], 6);
+        let x7 = aig.create_and(x[0], x6, 7);
+        let x8 = aig.create_and(!x5, x[0], 8);
+        aig.add_output(x7);
+        aig.add_output(x8);
+
+        let mut simulator = Simulator::new(&aig, 2);
+        simulator.randomize(7);
+        simulator.run();
+        assert_eq!(simulator.equivalence_candidates(), vec![vec![x5, x7]]);
+
+        // with a single pattern (all inputs 0) every AndNode is 0
+        let mut simulator = Simulator::new(&aig, 1);
+        simulator.run();
+        let all = vec![x4, x5, x6, x7, x8];
+        assert_eq!(simulator.equivalence_candidates(), vec![all]);
+    }
+}
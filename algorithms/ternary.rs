@@ -0,0 +1,332 @@
+use crate::aig_structure::aig::AIG;
+use crate::aig_structure::signal::Signal;
+use crate::algorithms::simulator::Trace;
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Not;
+
+/// Value of the three-valued (ternary) simulation: 0, 1 or unknown (X).
+/// X is either 0 or 1, so 0 AND X = 0, but 1 AND X = X and NOT X = X.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Ternary {
+    Zero,
+    One,
+    X,
+}
+
+impl Ternary {
+    pub fn and(self, other: Ternary) -> Ternary {
+        match (self, other) {
+            (Ternary::Zero, _) | (_, Ternary::Zero) => Ternary::Zero,
+            (Ternary::One, Ternary::One) => Ternary::One,
+            _ => Ternary::X,
+        }
+    }
+
+    /// the value of an inverted signal if complemented is true
+    pub fn invert_if(self, complemented: bool) -> Ternary {
+        if complemented { !self } else { self }
+    }
+
+    pub fn is_known(self) -> bool {
+        self != Ternary::X
+    }
+
+    /// the value as bool, None if it is unknown
+    pub fn to_bool(self) -> Option<bool> {
+        match self {
+            Ternary::Zero => Some(false),
+            Ternary::One => Some(true),
+            Ternary::X => None,
+        }
+    }
+
+    /// '0', '1' or 'x' (like in AIGER witnesses and VCD files)
+    pub fn symbol(self) -> char {
+        match self {
+            Ternary::Zero => '0',
+            Ternary::One => '1',
+            Ternary::X => 'x',
+        }
+    }
+}
+
+impl Not for Ternary {
+    type Output = Ternary;
+
+    fn not(self) -> Ternary {
+        match self {
+            Ternary::Zero => Ternary::One,
+            Ternary::One => Ternary::Zero,
+            Ternary::X => Ternary::X,
+        }
+    }
+}
+
+impl From<bool> for Ternary {
+    fn from(value: bool) -> Self {
+        if value { Ternary::One } else { Ternary::Zero }
+    }
+}
+
+/// None (e.g. an 'x' in a stimulus or an uninitialized latch) is X
+impl From<Option<bool>> for Ternary {
+    fn from(value: Option<bool>) -> Self {
+        value.map_or(Ternary::X, Ternary::from)
+    }
+}
+
+impl fmt::Display for Ternary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
+/// What the latch analysis found out about a latch.
+/// Constant: the latch has this value in every reachable state (it never leaves its reset value)
+/// Stuck: the latch has this value in every reachable state from this cycle on
+/// Varying: the latch can change its value (or the analysis could not prove anything else)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LatchStatus {
+    Constant(bool),
+    Stuck { value: bool, cycle: usize },
+    Varying,
+}
+
+impl fmt::Display for LatchStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LatchStatus::Constant(value) => write!(f, "constant {}", u8::from(*value)),
+            LatchStatus::Stuck { value, cycle } => write!(f, "stuck at {} from cycle {cycle}", u8::from(*value)),
+            LatchStatus::Varying => write!(f, "varying"),
+        }
+    }
+}
+
+/// Result of the latch analysis.
+/// states: the ternary latch states from the reset state on, with unknown inputs in every cycle. Every
+///         state contains all concrete states that are reachable in that cycle.
+/// loop_start: the cycle whose state comes again after the last state, so the states repeat from there
+///             on forever. None if no state repeated within the maximum number of cycles.
+/// status: the status of every latch in AIGER order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LatchAnalysis {
+    pub states: Vec<Vec<Ternary>>,
+    pub loop_start: Option<usize>,
+    pub status: Vec<LatchStatus>,
+}
+
+/// Three-valued simulation of an AIG. Unknown inputs and latches are X and X is propagated through the
+/// AndNodes, so a known value holds for every assignment of the unknown values. The result can be too
+/// pessimistic (x AND NOT x is X instead of 0), but never wrong.
+/// aig: the graph
+/// values: value of every node after evaluate, the constant included (always 0)
+/// order: the AndNodes in the order of AIG::topological_sort
+///
+/// The AIG has to be consistent (see `check`), otherwise evaluate panics.
+pub struct TernarySimulator<'a> {
+    pub aig: &'a AIG,
+    pub values: HashMap<usize, Ternary>,
+    order: Vec<usize>,
+}
+
+impl<'a> TernarySimulator<'a> {
+    pub fn new(aig: &'a AIG) -> Self {
+        let order = aig.topological_sort().into_iter().filter(|node| aig.node_map.contains_key(node)).collect();
+        TernarySimulator { aig, values: HashMap::new(), order }
+    }
+
+    /// Compute the values of all nodes for the given input values and latch state (both in AIGER order).
+    /// Missing values are X.
+    pub fn evaluate(&mut self, inputs: &[Ternary], state: &[Ternary]) {
+        self.values.clear();
+        self.values.insert(0, Ternary::Zero);
+        for (i, input) in self.aig.inputs.iter().enumerate() {
+            self.values.insert(input.index(), inputs.get(i).copied().unwrap_or(Ternary::X));
+        }
+        for (i, latch) in self.aig.latches.iter().enumerate() {
+            self.values.insert(latch.index, state.get(i).copied().unwrap_or(Ternary::X));
+        }
+
+        for &node_idx in &self.order {
+            let node = &self.aig.node_map[&node_idx];
+            let value = self.signal_value(node.left_signal).and(self.signal_value(node.right_signal));
+            self.values.insert(node_idx, value);
+        }
+    }
+
+    /// the value of a signal after evaluate, inverted if the signal is inverted
+    pub fn signal_value(&self, signal: Signal) -> Ternary {
+        let value = self.values.get(&signal.index())
+            .unwrap_or_else(|| panic!("node {} is neither an input, a latch nor an AndNode", signal.index()));
+        value.invert_if(signal.is_complemented())
+    }
+
+    pub fn output_values(&self) -> Vec<Ternary> {
+        self.aig.outputs.iter().map(|&output| self.signal_value(output)).collect()
+    }
+
+    /// the values of the next state signals of the latches
+    pub fn next_state(&self) -> Vec<Ternary> {
+        self.aig.latches.iter().map(|latch| self.signal_value(latch.next)).collect()
+    }
+
+    /// The reset state: the reset values of the latches, uninitialized latches are X.
+    pub fn reset_state(&self) -> Vec<Ternary> {
+        self.aig.latches.iter().map(|latch| Ternary::from(latch.init)).collect()
+    }
+
+    /// Simulate one input vector per cycle, starting with the given latch state (like
+    /// Simulator::simulate_cycles). Unknown values are propagated from cycle to cycle through the latches.
+    pub fn simulate_cycles(&mut self, initial_state: &[Ternary], vectors: &[Vec<Ternary>]) -> Trace<Ternary> {
+        let mut trace = Trace::default();
+        let mut state = initial_state.to_vec();
+        for vector in vectors {
+            self.evaluate(vector, &state);
+            let next_state = self.next_state();
+            trace.inputs.push(vector.clone());
+            trace.latches.push(std::mem::replace(&mut state, next_state));
+            trace.outputs.push(self.output_values());
+        }
+        trace
+    }
+
+    /// Find constant and stuck latches. The latches are simulated from the reset state with unknown inputs
+    /// until a state repeats (at most max_cycles cycles). From then on the states repeat forever, so a latch
+    /// that has the same known value in all states of the loop is stuck at this value from the first cycle
+    /// after its last different value.
+    /// If no state repeats, the states are widened instead: every latch that changes its value becomes X.
+    /// This converges after at most one cycle per latch and finds the constant latches (but no stuck ones).
+    pub fn analyze_latches(&mut self, max_cycles: usize) -> LatchAnalysis {
+        let mut states = vec![self.reset_state()];
+        let mut seen: HashMap<Vec<Ternary>, usize> = HashMap::from([(states[0].clone(), 0)]);
+        let mut loop_start = None;
+        while states.len() <= max_cycles {
+            self.evaluate(&[], &states[states.len() - 1]);
+            let next_state = self.next_state();
+            if let Some(&cycle) = seen.get(&next_state) {
+                loop_start = Some(cycle);
+                break;
+            }
+            seen.insert(next_state.clone(), states.len());
+            states.push(next_state);
+        }
+
+        let status = match loop_start {
+            Some(loop_start) => (0..self.aig.latches.len())
+                .map(|latch| Self::latch_status(&states, loop_start, latch))
+                .collect(),
+            None => self.widen(states[0].clone()).into_iter()
+                .map(|value| value.to_bool().map_or(LatchStatus::Varying, LatchStatus::Constant))
+                .collect(),
+        };
+        LatchAnalysis { states, loop_start, status }
+    }
+
+    fn latch_status(states: &[Vec<Ternary>], loop_start: usize, latch: usize) -> LatchStatus {
+        let value = states[loop_start][latch];
+        let Some(known) = value.to_bool() else {
+            return LatchStatus::Varying;
+        };
+        if states[loop_start..].iter().any(|state| state[latch] != value) {
+            return LatchStatus::Varying;
+        }
+        // the first cycle of the last run of this value before the loop
+        let cycle = states[..loop_start].iter().rposition(|state| state[latch] != value).map_or(0, |cycle| cycle + 1);
+        if cycle == 0 {
+            LatchStatus::Constant(known)
+        } else {
+            LatchStatus::Stuck { value: known, cycle }
+        }
+    }
+
+    /// Over-approximation of all reachable states: the next state is joined with the current one (different
+    /// values become X) until nothing changes anymore.
+    fn widen(&mut self, mut state: Vec<Ternary>) -> Vec<Ternary> {
+        loop {
+            self.evaluate(&[], &state);
+            let joined: Vec<Ternary> = state.iter().zip(self.next_state())
+                .map(|(&current, next)| if current == next { current } else { Ternary::X })
+                .collect();
+            if joined == state {
+                return state;
+            }
+            state = joined;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LatchStatus, Ternary, TernarySimulator};
+    use crate::aig_structure::aig::AIG;
+    use crate::aig_structure::signal::Signal;
+
+    /// x2: uninitialized, toggles
+    /// x3: reset 0, next x3 and x1 (never becomes 1)
+    /// x4: reset 1, next 0 (stuck at 0 from cycle 1)
+    /// x5: reset 0, toggles
+    /// outputs: x2 and x3, x2 and x5
+    fn latches() -> AIG {
+        let mut aig = AIG::new();
+        let x1 = aig.add_input(1);
+        let x2 = aig.add_latch(2, None);
+        let x3 = aig.add_latch(3, Some(false));
+        aig.add_latch(4, Some(true));
+        let x5 = aig.add_latch(5, Some(false));
+        let x6 = aig.create_and(x3, x1, 6);
+        aig.set_latch_next(0, !x2);
+        aig.set_latch_next(1, x6);
+        aig.set_latch_next(2, Signal::new(0, false));
+        aig.set_latch_next(3, !x5);
+        let x7 = aig.create_and(x2, x3, 7);
+        let x8 = aig.create_and(x2, x5, 8);
+        aig.add_output(x7);
+        aig.add_output(x8);
+        aig
+    }
+
+    #[test]
+    fn unknown_reset_values_stay_unknown() {
+        use Ternary::{One, Zero, X};
+        let aig = latches();
+        let mut simulator = TernarySimulator::new(&aig);
+        let reset = simulator.reset_state();
+        assert_eq!(reset, vec![X, Zero, One, Zero]);
+
+        let trace = simulator.simulate_cycles(&reset, &[vec![One], vec![X], vec![One]]);
+        assert_eq!(trace.latches, vec![vec![X, Zero, One, Zero], vec![X, Zero, Zero, One], vec![X, Zero, Zero, Zero]]);
+        // x2 and x3 is 0 because x3 is, x2 and x5 is only known while x5 is 0
+        assert_eq!(trace.outputs, vec![vec![Zero, Zero], vec![Zero, X], vec![Zero, Zero]]);
+
+        // a missing input value is X as well
+        simulator.evaluate(&[], &[One, One, One, One]);
+        assert_eq!(simulator.signal_value(Signal::new(6, false)), X);
+        assert_eq!(simulator.output_values(), vec![One, One]);
+    }
+
+    #[test]
+    fn latch_analysis_finds_constant_and_stuck_latches() {
+        let aig = latches();
+        let analysis = TernarySimulator::new(&aig).analyze_latches(10);
+        // the state of cycle 1 comes again after cycle 2
+        assert_eq!(analysis.loop_start, Some(1));
+        assert_eq!(analysis.states.len(), 3);
+        assert_eq!(analysis.status, vec![
+            LatchStatus::Varying,
+            LatchStatus::Constant(false),
+            LatchStatus::Stuck { value: false, cycle: 1 },
+            LatchStatus::Varying,
+        ]);
+
+        // without a repeated state the widening still finds the constant latch, but not the stuck one
+        let analysis = TernarySimulator::new(&aig).analyze_latches(1);
+        assert_eq!(analysis.loop_start, None);
+        assert_eq!(analysis.status, vec![
+            LatchStatus::Varying,
+            LatchStatus::Constant(false),
+            LatchStatus::Varying,
+            LatchStatus::Varying,
+        ]);
+    }
+}
@@ -0,0 +1,366 @@
+use std::fmt;
+
+/// Masks of the projection functions x_0 ... x_5 in a 64-bit word.
+/// Example: x_0 is 1 for every odd minterm -> 1010... = 0xAAAA...
+const VAR_MASKS: [u64; 6] = [
+    0xAAAA_AAAA_AAAA_AAAA,
+    0xCCCC_CCCC_CCCC_CCCC,
+    0xF0F0_F0F0_F0F0_F0F0,
+    0xFF00_FF00_FF00_FF00,
+    0xFFFF_0000_FFFF_0000,
+    0xFFFF_FFFF_0000_0000,
+];
+
+/// Masks to swap the adjacent variables x_i and x_i+1 in a 64-bit word (same as in ABC).
+/// [bits that stay, bits that move up, bits that move down]
+const SWAP_MASKS: [[u64; 3]; 5] = [
+    [0x9999_9999_9999_9999, 0x2222_2222_2222_2222, 0x4444_4444_4444_4444],
+    [0xC3C3_C3C3_C3C3_C3C3, 0x0C0C_0C0C_0C0C_0C0C, 0x3030_3030_3030_3030],
+    [0xF00F_F00F_F00F_F00F, 0x00F0_00F0_00F0_00F0, 0x0F00_0F00_0F00_0F00],
+    [0xFF00_00FF_FF00_00FF, 0x0000_FF00_0000_FF00, 0x00FF_0000_00FF_0000],
+    [0xFFFF_0000_0000_FFFF, 0x0000_0000_FFFF_0000, 0x0000_FFFF_0000_0000],
+];
+
+/// Truth table of a Boolean function. Bit m is the value of the function for the input assignment m,
+/// where variable i has the value of bit i of m.
+///
+/// Word: up to 6 variables fit into one 64-bit word. Functions with less than 6 variables are repeated
+///       over the whole word, so and/not work without masking.
+/// Dynamic: more than 6 variables, 2^(num_vars - 6) words. words[0] contains the minterms 0..63.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub enum TruthTable {
+    Word { num_vars: u8, bits: u64 },
+    Dynamic { num_vars: u8, words: Vec<u64> },
+}
+
+impl TruthTable {
+    /// the maximum number of variables for a word-packed truth table
+    pub const MAX_WORD_VARS: usize = 6;
+
+    fn num_words(num_vars: usize) -> usize {
+        if num_vars <= Self::MAX_WORD_VARS { 1 } else { 1 << (num_vars - Self::MAX_WORD_VARS) }
+    }
+
+    fn from_words(num_vars: usize, mut words: Vec<u64>) -> Self {
+        if num_vars <= Self::MAX_WORD_VARS {
+            TruthTable::Word { num_vars: num_vars as u8, bits: words[0] }
+        } else {
+            words.truncate(Self::num_words(num_vars));
+            TruthTable::Dynamic { num_vars: num_vars as u8, words }
+        }
+    }
+
+    /// the constant function 0 or 1
+    pub fn constant(num_vars: usize, value: bool) -> Self {
+        let word = if value { u64::MAX } else { 0 };
+        Self::from_words(num_vars, vec![word; Self::num_words(num_vars)])
+    }
+
+    /// the projection function x_var
+    pub fn var(num_vars: usize, var: usize) -> Self {
+        assert!(var < num_vars, "variable {var} does not exist in a function with {num_vars} variables");
+        let words = (0..Self::num_words(num_vars))
+            .map(|w| {
+                if var < Self::MAX_WORD_VARS {
+                    VAR_MASKS[var]
+                } else if (w >> (var - Self::MAX_WORD_VARS)) & 1 == 1 {
+                    u64::MAX
+                } else {
+                    0
+                }
+            })
+            .collect();
+        Self::from_words(num_vars, words)
+    }
+
+    pub fn num_vars(&self) -> usize {
+        match self {
+            TruthTable::Word { num_vars, .. } | TruthTable::Dynamic { num_vars, .. } => *num_vars as usize,
+        }
+    }
+
+    pub fn words(&self) -> &[u64] {
+        match self {
+            TruthTable::Word { bits, .. } => std::slice::from_ref(bits),
+            TruthTable::Dynamic { words, .. } => words,
+        }
+    }
+
+    /// the value of the function for the input assignment `minterm`
+    pub fn bit(&self, minterm: usize) -> bool {
+        (self.words()[minterm / 64] >> (minterm % 64)) & 1 == 1
+    }
+
+    /// the complement of the function if `complement` is set, otherwise a copy
+    pub fn complement(&self, complement: bool) -> Self {
+        let mask = if complement { u64::MAX } else { 0 };
+        Self::from_words(self.num_vars(), self.words().iter().map(|w| w ^ mask).collect())
+    }
+
+    /// and of two functions over the same variables
+    pub fn and(&self, other: &TruthTable) -> Self {
+        assert_eq!(self.num_vars(), other.num_vars(), "truth tables must have the same number of variables");
+        Self::from_words(self.num_vars(), self.words().iter().zip(other.words()).map(|(a, b)| a & b).collect())
+    }
+
+    /// Express the function over new variables. `from` are the leaves of this function (variable i = from[i]),
+    /// `to` are the new leaves. Both are sorted and `from` has to be a subset of `to`.
+    /// Example: f = x_0 and x_1 over the leaves [3, 7] expanded to [3, 5, 7] is x_0 and x_2.
+    pub fn expand(&self, from: &[u32], to: &[u32]) -> Self {
+        // pos[i]: the new variable of the old variable i
+        let pos: Vec<usize> = from.iter()
+            .map(|leaf| to.iter().position(|l| l == leaf).expect("leaves of the truth table are not a subset of the new leaves"))
+            .collect();
+
+        if to.len() <= Self::MAX_WORD_VARS {
+            // move every variable to its new position, beginning with the highest one. The variables
+            // between the old and the new position are don't cares at this point, so swapping is enough.
+            let mut bits = self.words()[0];
+            for i in (0..pos.len()).rev() {
+                for var in i..pos[i] {
+                    bits = Self::swap_adjacent(bits, var);
+                }
+            }
+            return TruthTable::Word { num_vars: to.len() as u8, bits };
+        }
+
+        // more than 6 variables: look up every minterm of the new function in the old function
+        let mut words = vec![0u64; Self::num_words(to.len())];
+        for (minterm, word) in (0..1usize << to.len()).map(|m| (m, m / 64)) {
+            let old_minterm = pos.iter().enumerate().fold(0, |acc, (i, &p)| acc | (((minterm >> p) & 1) << i));
+            if self.bit(old_minterm) {
+                words[word] |= 1 << (minterm % 64);
+            }
+        }
+        Self::from_words(to.len(), words)
+    }
+
+    /// swap the variables x_var and x_var+1 (var < 5) of a word-packed truth table
+    pub(crate) fn swap_adjacent(bits: u64, var: usize) -> u64 {
+        let [stay, up, down] = SWAP_MASKS[var];
+        let shift = 1 << var;
+        (bits & stay) | ((bits & up) << shift) | ((bits & down) >> shift)
+    }
+
+    /// swap two arbitrary variables x_a and x_b of a word-packed truth table
+    pub(crate) fn swap_vars(mut bits: u64, a: usize, b: usize) -> u64 {
+        let (low, high) = if a < b { (a, b) } else { (b, a) };
+        // move x_low up to x_high, then the old x_high (now at high - 1) down to x_low
+        for var in low..high {
+            bits = Self::swap_adjacent(bits, var);
+        }
+        for var in (low..high.saturating_sub(1)).rev() {
+            bits = Self::swap_adjacent(bits, var);
+        }
+        bits
+    }
+
+    /// negate the variable x_var of a word-packed truth table (swap the halves where x_var = 0 and x_var = 1)
+    pub(crate) fn flip_var(bits: u64, var: usize) -> u64 {
+        let shift = 1 << var;
+        ((bits & VAR_MASKS[var]) >> shift) | ((bits & !VAR_MASKS[var]) << shift)
+    }
+
+    /// hexadecimal representation, the highest minterm first (like ABC). At least one digit.
+    pub fn to_hex(&self) -> String {
+        match self {
+            TruthTable::Word { num_vars, bits } => {
+                let num_bits = 1usize << num_vars;
+                let digits = (num_bits / 4).max(1);
+                let mask = if num_bits == 64 { u64::MAX } else { (1u64 << num_bits) - 1 };
+                format!("{:0digits$x}", bits & mask)
+            }
+            TruthTable::Dynamic { words, .. } => words.iter().rev().map(|w| format!("{w:016x}")).collect(),
+        }
+    }
+
+    /// binary representation, the highest minterm first
+    pub fn to_binary(&self) -> String {
+        (0..1usize << self.num_vars()).rev().map(|m| if self.bit(m) { '1' } else { '0' }).collect()
+    }
+
+    /// Parse the hexadecimal representation of to_hex (an optional "0x" prefix is allowed). None if the
+    /// string has not exactly the number of digits of to_hex, contains other characters or, for less than
+    /// 2 variables, the single digit has more than 2^num_vars bits (e.g. "6" for 1 variable).
+    pub fn from_hex(num_vars: usize, hex: &str) -> Option<Self> {
+        let hex = hex.strip_prefix("0x").unwrap_or(hex);
+        let num_bits = 1usize << num_vars;
+        if hex.len() != (num_bits / 4).max(1) {
+            return None;
+        }
+        let mut words = vec![0u64; Self::num_words(num_vars)];
+        // the last digit contains the minterms 0..3
+        for (i, digit) in hex.chars().rev().enumerate() {
+            let value = digit.to_digit(16)? as u64;
+            if num_bits < 4 && value >> num_bits != 0 {
+                return None;
+            }
+            words[i / 16] |= value << (4 * (i % 16));
+        }
+        Some(Self::replicate(num_vars, words))
+    }
+
+    /// Parse the binary representation of to_binary (an optional "0b" prefix is allowed). None if the
+    /// string does not have 2^num_vars digits or contains other characters.
+    pub fn from_binary(num_vars: usize, binary: &str) -> Option<Self> {
+        let binary = binary.strip_prefix("0b").unwrap_or(binary);
+        if binary.len() != 1usize << num_vars {
+            return None;
+        }
+        let mut words = vec![0u64; Self::num_words(num_vars)];
+        for (minterm, digit) in binary.chars().rev().enumerate() {
+            match digit {
+                '0' => {}
+                '1' => words[minterm / 64] |= 1 << (minterm % 64),
+                _ => return None,
+            }
+        }
+        Some(Self::replicate(num_vars, words))
+    }
+
+    /// repeat the pattern of a function with less than 6 variables over the whole word
+    fn replicate(num_vars: usize, mut words: Vec<u64>) -> Self {
+        let mut width = 1usize << num_vars;
+        while width < 64 {
+            words[0] |= words[0] << width;
+            width *= 2;
+        }
+        Self::from_words(num_vars, words)
+    }
+}
+
+impl fmt::Debug for TruthTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{}", self.to_hex())
+    }
+}
+
+impl fmt::Display for TruthTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{}", self.to_hex())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TruthTable;
+    use crate::algorithms::cut_enumerator::CutEnumerator;
+    use crate::input_output::read_aiger::AigerReader;
+    use crate::AIG;
+    use std::collections::HashMap;
+
+    /// xorshift, enough for random test functions
+    fn random_words(seed: &mut u64, count: usize) -> Vec<u64> {
+        (0..count).map(|_| {
+            *seed ^= *seed << 13;
+            *seed ^= *seed >> 7;
+            *seed ^= *seed << 17;
+            *seed
+        }).collect()
+    }
+
+    /// a random function, replicate expects only the lowest 2^num_vars bits to be set
+    fn random_function(seed: &mut u64, num_vars: usize) -> TruthTable {
+        let mut words = random_words(seed, TruthTable::num_words(num_vars));
+        if num_vars < TruthTable::MAX_WORD_VARS {
+            words[0] &= (1 << (1 << num_vars)) - 1;
+        }
+        TruthTable::replicate(num_vars, words)
+    }
+
+    #[test]
+    fn swap_adjacent_and_flip_var_move_the_minterms() {
+        let mut seed = 1;
+        for bits in random_words(&mut seed, 20) {
+            for var in 0..5 {
+                let swapped = TruthTable::swap_adjacent(bits, var);
+                for m in 0..64usize {
+                    let (a, b) = ((m >> var) & 1, (m >> (var + 1)) & 1);
+                    let source = (m & !(3 << var)) | (b << var) | (a << (var + 1));
+                    assert_eq!((swapped >> m) & 1, (bits >> source) & 1, "swap x{var}, minterm {m}");
+                }
+            }
+            for var in 0..6 {
+                let flipped = TruthTable::flip_var(bits, var);
+                for m in 0..64usize {
+                    assert_eq!((flipped >> m) & 1, (bits >> (m ^ (1 << var))) & 1, "flip x{var}, minterm {m}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn expand_keeps_the_function() {
+        let mut seed = 7;
+        // word to word, word to dynamic and dynamic to dynamic
+        let cases: [(&[u32], &[u32]); 4] = [
+            (&[3, 7], &[3, 5, 7]),
+            (&[2, 4, 9], &[1, 2, 4, 6, 8, 9]),
+            (&[1, 5, 6, 9], &[1, 2, 3, 5, 6, 7, 8, 9]),
+            (&[1, 2, 3, 4, 5, 6, 7], &[1, 2, 3, 4, 5, 6, 7, 8, 9]),
+        ];
+        for (from, to) in cases {
+            let function = random_function(&mut seed, from.len());
+            let expanded = function.expand(from, to);
+            assert_eq!(expanded.num_vars(), to.len());
+            for minterm in 0..1usize << to.len() {
+                let old_minterm = from.iter().enumerate()
+                    .map(|(i, leaf)| ((minterm >> to.iter().position(|l| l == leaf).unwrap()) & 1) << i)
+                    .sum();
+                assert_eq!(expanded.bit(minterm), function.bit(old_minterm), "{from:?} -> {to:?}, minterm {minterm}");
+            }
+        }
+    }
+
+    #[test]
+    fn hex_round_trip() {
+        let mut seed = 3;
+        for num_vars in 0..=8 {
+            let function = random_function(&mut seed, num_vars);
+            let hex = function.to_hex();
+            assert_eq!(hex.len(), ((1usize << num_vars) / 4).max(1));
+            assert_eq!(TruthTable::from_hex(num_vars, &hex), Some(function.clone()));
+            assert_eq!(TruthTable::from_hex(num_vars, &format!("0x{hex}")), Some(function.clone()));
+            assert_eq!(TruthTable::from_binary(num_vars, &function.to_binary()), Some(function));
+        }
+        // a function with less than 6 variables is repeated over the whole word
+        assert_eq!(TruthTable::from_hex(2, "8"), Some(TruthTable::Word { num_vars: 2, bits: 0x8888_8888_8888_8888 }));
+        assert_eq!(TruthTable::from_hex(2, "08"), None);
+        assert_eq!(TruthTable::from_hex(7, "g0000000000000000000000000000000"), None);
+    }
+
+    /// the value of a node for the given values of the leaves of a cut
+    fn evaluate(aig: &AIG, node: usize, leaves: &HashMap<usize, bool>) -> bool {
+        if let Some(&value) = leaves.get(&node) {
+            return value;
+        }
+        let Some(and_node) = aig.node_map.get(&node) else {
+            assert_eq!(node, 0, "node {node} is not inside the cut");
+            return false;
+        };
+        [and_node.left_signal, and_node.right_signal].iter()
+            .all(|signal| evaluate(aig, signal.index(), leaves) ^ signal.is_complemented())
+    }
+
+    #[test]
+    fn cut_truth_tables_match_simulation() {
+        let aig = AigerReader::from_file("debug/test_files/aigs/aigverse_3.aig").unwrap().aig;
+        let mut cut_enumerator = CutEnumerator::new(&aig);
+        cut_enumerator.compute_truth_tables = true;
+        cut_enumerator.enumerate_cuts(8, &aig.inputs);
+
+        let mut checked = [0, 0];
+        for (&node, cuts) in &cut_enumerator.cuts {
+            for cut in cuts {
+                let truth_table = cut.truth_table.as_ref().unwrap();
+                assert_eq!(truth_table.num_vars(), cut.len());
+                for minterm in 0..1usize << cut.len() {
+                    let leaves = cut.iter().enumerate().map(|(i, leaf)| (leaf, (minterm >> i) & 1 == 1)).collect();
+                    assert_eq!(truth_table.bit(minterm), evaluate(&aig, node, &leaves), "node {node}, cut {:?}", cut.leaves());
+                }
+                checked[usize::from(cut.len() > TruthTable::MAX_WORD_VARS)] += 1;
+            }
+        }
+        assert!(checked[0] > 0 && checked[1] > 0, "cuts with at most and with more than 6 leaves: {checked:?}");
+    }
+}
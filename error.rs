@@ -0,0 +1,53 @@
+use std::fmt;
+use std::io;
+
+/// All errors that can be returned by crust.
+/// Io: reading or writing a file failed
+/// InvalidHeader: the first line of an AIGER file is not a valid binary AIGER header
+/// Parse: a value in an AIGER file could not be parsed
+/// Graphviz: the `dot` command did not finish successfully
+/// InvalidArgument: a parameter is out of its valid range
+/// InconsistentAig: the AIG does not pass AIG::check (the report of the check)
+/// Mismatch: a result differs from the expected result (e.g. the truth table of an output)
+#[derive(Debug)]
+pub enum CrustError {
+    Io(io::Error),
+    InvalidHeader(String),
+    Parse { what: String, value: String },
+    Graphviz(String),
+    InvalidArgument(String),
+    InconsistentAig(String),
+    Mismatch(String),
+}
+
+/// Shorthand for results with a `CrustError`.
+pub type Result<T> = std::result::Result<T, CrustError>;
+
+impl fmt::Display for CrustError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CrustError::Io(err) => write!(f, "I/O error: {err}"),
+            CrustError::InvalidHeader(header) => write!(f, "invalid AIGER header: '{header}'"),
+            CrustError::Parse { what, value } => write!(f, "could not parse {what}: '{value}'"),
+            CrustError::Graphviz(status) => write!(f, "dot failed: {status}"),
+            CrustError::InvalidArgument(message) => write!(f, "invalid argument: {message}"),
+            CrustError::InconsistentAig(report) => write!(f, "inconsistent AIG: {report}"),
+            CrustError::Mismatch(message) => write!(f, "mismatch: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for CrustError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CrustError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for CrustError {
+    fn from(err: io::Error) -> Self {
+        CrustError::Io(err)
+    }
+}
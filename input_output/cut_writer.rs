@@ -0,0 +1,144 @@
+use crate::algorithms::cut::Cut;
+use crate::algorithms::cut_enumerator::CutEnumerator;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+/// Output format for cuts.
+/// Text: the Debug output of the sorted cuts, e.g. {3: [{1, 2}, {3}]} (human readable)
+/// Json: one object per cut in a "cuts" array
+/// Csv: one line per cut with a header line
+/// In every format the nodes are sorted and the leaves of every cut are sorted, so two runs on the
+/// same AIG give the same file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CutFormat {
+    #[default]
+    Text,
+    Json,
+    Csv,
+}
+
+impl FromStr for CutFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" | "txt" => Ok(CutFormat::Text),
+            "json" => Ok(CutFormat::Json),
+            "csv" => Ok(CutFormat::Csv),
+            _ => Err(format!("unknown cut format '{s}' (expected text, json or csv)")),
+        }
+    }
+}
+
+/// Write the cuts of several nodes (e.g. CutEnumerator::sorted_cuts) in the given format.
+/// Every cut is written with its node id, its leaves, its size and, if they were computed, its truth
+/// table (hex, like ABC), its cost data and its volume (JSON and CSV only).
+/// volumes: the enumerator that found the cuts. If it is given, the volume of every cut is computed with
+///          CutEnumerator::cut_volume while it is written. The volumes are not stored, most cuts never need one.
+///
+/// JSON: {"cuts": [{"node": 3, "leaves": [1, 2], "size": 2, "truth_table": "0x8",
+///                  "cost": {"depth": 1, "area_flow": 1, "exact_area": 1, "leaf_count": 2},
+///                  "volume": {"internal": [], "external_fanout": [], "fanout_free": true}}, ...]}
+///       truth_table, cost and volume are left out if they were not computed.
+/// CSV:  node,size,leaves,truth_table,depth,area_flow,exact_area,leaf_count,internal,external_fanout,fanout_free
+///       The leaves and node lists are separated by spaces. Missing values are empty fields.
+pub fn write_cuts<W: Write>(writer: &mut W, format: CutFormat, cuts: &BTreeMap<usize, &Vec<Cut>>, volumes: Option<&CutEnumerator>) -> io::Result<()> {
+    match format {
+        CutFormat::Text => writeln!(writer, "{:?}", cuts),
+        CutFormat::Json => write_json(writer, cuts, volumes),
+        CutFormat::Csv => write_csv(writer, cuts, volumes),
+    }
+}
+
+fn write_json<W: Write>(writer: &mut W, cuts: &BTreeMap<usize, &Vec<Cut>>, volumes: Option<&CutEnumerator>) -> io::Result<()> {
+    writeln!(writer, "{{")?;
+    writeln!(writer, "  \"cuts\": [")?;
+    let all_cuts: Vec<(usize, &Cut)> = cuts.iter().flat_map(|(&node, cuts)| cuts.iter().map(move |cut| (node, cut))).collect();
+    for (i, (node, cut)) in all_cuts.iter().enumerate() {
+        let leaves: Vec<usize> = cut.iter().collect();
+        write!(writer, "    {{\"node\": {node}, \"leaves\": [{}], \"size\": {}", join(&leaves, ", "), cut.len())?;
+        if let Some(truth_table) = &cut.truth_table {
+            write!(writer, ", \"truth_table\": \"{truth_table}\"")?;
+        }
+        if let Some(cost) = &cut.cost {
+            write!(
+                writer,
+                ", \"cost\": {{\"depth\": {}, \"area_flow\": {}, \"exact_area\": {}, \"leaf_count\": {}}}",
+                cost.depth, json_number(cost.area_flow), cost.exact_area, cost.leaf_count
+            )?;
+        }
+        if let Some(volume) = volumes.map(|cut_enumerator| cut_enumerator.cut_volume(*node, cut)) {
+            write!(
+                writer,
+                ", \"volume\": {{\"internal\": [{}], \"external_fanout\": [{}], \"fanout_free\": {}}}",
+                join(&volume.internal, ", "), join(&volume.external_fanout, ", "), volume.fanout_free
+            )?;
+        }
+        let separator = if i + 1 < all_cuts.len() { "," } else { "" };
+        writeln!(writer, "}}{separator}")?;
+    }
+    writeln!(writer, "  ]")?;
+    writeln!(writer, "}}")
+}
+
+/// the numbers separated by the separator
+fn join(values: &[usize], separator: &str) -> String {
+    values.iter().map(|value| value.to_string()).collect::<Vec<_>>().join(separator)
+}
+
+/// JSON has no infinity or NaN
+fn json_number(value: f64) -> String {
+    if value.is_finite() { value.to_string() } else { "null".to_string() }
+}
+
+fn write_csv<W: Write>(writer: &mut W, cuts: &BTreeMap<usize, &Vec<Cut>>, volumes: Option<&CutEnumerator>) -> io::Result<()> {
+    writeln!(writer, "node,size,leaves,truth_table,depth,area_flow,exact_area,leaf_count,internal,external_fanout,fanout_free")?;
+    for (&node, node_cuts) in cuts {
+        for cut in node_cuts.iter() {
+            let leaves: Vec<usize> = cut.iter().collect();
+            let truth_table = cut.truth_table.as_ref().map(|tt| tt.to_string()).unwrap_or_default();
+            let cost = match &cut.cost {
+                Some(cost) => format!("{},{},{},{}", cost.depth, cost.area_flow, cost.exact_area, cost.leaf_count),
+                None => ",,,".to_string(),
+            };
+            let volume = match volumes.map(|cut_enumerator| cut_enumerator.cut_volume(node, cut)) {
+                Some(volume) => format!("{},{},{}", join(&volume.internal, " "), join(&volume.external_fanout, " "), volume.fanout_free),
+                None => ",,".to_string(),
+            };
+            writeln!(writer, "{node},{},{},{truth_table},{cost},{volume}", cut.len(), join(&leaves, " "))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{write_cuts, CutFormat};
+    use crate::aig_structure::aig::AIG;
+    use crate::algorithms::cut_enumerator::CutEnumerator;
+
+    #[test]
+    fn volumes_are_computed_while_writing() {
+        // x4 = x1 and x2, x5 = x4 and x3: the cut {1, 2, 3} of x5 contains x4
+        let mut aig = AIG::new();
+        let x1 = aig.add_input(1);
+        let x2 = aig.add_input(2);
+        let x3 = aig.add_input(3);
+        let x4 = aig.create_and(x1, x2, 4);
+        let x5 = aig.create_and(x4, x3, 5);
+        aig.add_output(x5);
+        let mut cut_enumerator = CutEnumerator::new(&aig);
+        cut_enumerator.enumerate_cuts(3, &aig.inputs);
+        let cuts = cut_enumerator.sorted_cuts();
+
+        let write = |volumes| {
+            let mut output = Vec::new();
+            write_cuts(&mut output, CutFormat::Csv, &cuts, volumes).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+        let with_volumes = write(Some(&cut_enumerator));
+        assert!(with_volumes.lines().any(|line| line.starts_with("5,3,1 2 3,") && line.ends_with(",4,,true")), "{with_volumes}");
+        assert!(write(None).lines().skip(1).all(|line| line.ends_with(",,")));
+    }
+}
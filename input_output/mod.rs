@@ -1,2 +1,5 @@
+pub mod cut_writer;
 pub mod read_aiger;
+pub mod stimulus;
+pub mod trace_writer;
 pub mod visualizer;
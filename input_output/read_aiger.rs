@@ -1,24 +1,20 @@
-#![allow(warnings)]
-
+use std::collections::HashMap;
 use std::fs::File;
-use std::io::{self, BufReader, BufRead, Read};
+use std::io::{BufReader, BufRead, Read};
 use crate::aig_structure::aig::AIG;
 use crate::aig_structure::signal::Signal;
+use crate::error::{CrustError, Result};
 
 /// struct that builds an aig from an aiger file based on this paper: https://fmv.jku.at/aiger/FORMAT.aiger
-/// aig: aig from aiger file.
-/// inputs: vector that contains all input signals
-/// outputs: vector that contains all input outputs
+/// aig: aig from aiger file. It also contains the input and output signals.
 pub struct AigerReader {
     pub aig: AIG,
-    pub inputs: Vec<Signal>,
-    pub outputs: Vec<Signal>,
 }
 
 impl AigerReader {
 
     /// read AIG from file
-    pub fn from_file(filename: &str) -> io::Result<Self> {
+    pub fn from_file(filename: &str) -> Result<Self> {
         let file = File::open(filename)?;
         let mut reader = BufReader::new(file); //BufReader reduces the number of system calls by buffering data internally
 
@@ -32,36 +28,71 @@ impl AigerReader {
         // The literals are (rhs0, rhs1 und lhs). 
         // input literals = 2, 4, 6, .... 2*i --> input variable indices = literal / 2 or 1, 2, 3, .... i
         // and literal = 2*(I+L)+2, 4*(I+L)+4, .... 2*(I+L+A)   --> and variable indices = i + l + 1  or lhs / 2, da lhs = 2 * (i + l) + 2 * n
-        let to_signal = |lit: u64| {
-            let index = (lit / 2) as usize;
-            let inverted = lit % 2 == 1; // only odd literals are inverted
-            
-            Signal::new(index, inverted)
-        };
-
         // 1. Header lesen (erste Textzeile)
         let mut header_line = String::new();
         reader.read_line(&mut header_line)?;
-        let header_parts: Vec<&str> = header_line.trim().split_whitespace().collect();
+        let header_parts: Vec<&str> = header_line.split_whitespace().collect();
         if header_parts.len() < 6 || header_parts[0] != "aig" {
-            panic!("invalid header!");
+            return Err(CrustError::InvalidHeader(header_line.trim().to_string()));
         }
-        let i: usize = header_parts[2].parse().unwrap();
-        let l: usize = header_parts[3].parse().unwrap();
-        let o: usize = header_parts[4].parse().unwrap();
-        let a: usize = header_parts[5].parse().unwrap();
+        let m = parse_number::<usize>(header_parts[1], "header field M")?;
+        let i = parse_number::<usize>(header_parts[2], "header field I")?;
+        let l = parse_number::<usize>(header_parts[3], "header field L")?;
+        let o = parse_number::<usize>(header_parts[4], "header field O")?;
+        let a = parse_number::<usize>(header_parts[5], "header field A")?;
+        // every variable index has to fit into a Signal (31 bits), otherwise it would become a different node
+        let num_vars = i.checked_add(l).and_then(|n| n.checked_add(a));
+        let Some(num_vars) = num_vars.filter(|&n| m <= Signal::MAX_INDEX && n <= Signal::MAX_INDEX) else {
+            return Err(CrustError::InvalidHeader(header_line.trim().to_string()));
+        };
 
-        // 2. read outputs as ASCII
+        // Since Signal stores exactly this literal (index << 1 | complement), the literal only has to point
+        // to a variable that is defined: the constant, an input, a latch or an AND gate, and at most M.
+        // Only odd literals are inverted.
+        let max_index = m.min(num_vars);
+        let to_signal = |lit: u64| -> Result<Signal> {
+            if lit / 2 > max_index as u64 {
+                return Err(CrustError::Parse {
+                    what: format!("literal (no variable with this index, M = {m})"),
+                    value: lit.to_string(),
+                });
+            }
+            Ok(Signal::from_literal(lit as u32))
+        };
+
+        // 2. read latches as ASCII. In the binary format the latch literal itself is not stored,
+        // because the latches are numbered consecutively after the inputs: 2*(i+1), 2*(i+2), ....
+        // A line contains the next state literal and optionally the reset value (AIGER 1.9):
+        // 0, 1, or the latch literal itself if the latch is uninitialized.
+        let mut latches = Vec::new();
+        for k in 0..l {
+            let mut line = String::new();
+            reader.read_line(&mut line)?;
+            let mut parts = line.split_whitespace();
+            let next = parse_number::<u64>(parts.next().unwrap_or(""), "latch next literal")?;
+            let latch_literal = 2 * (i + k + 1) as u64;
+            let init = match parts.next() {
+                None | Some("0") => Some(false),
+                Some("1") => Some(true),
+                Some(value) if parse_number::<u64>(value, "latch reset value")? == latch_literal => None,
+                Some(value) => {
+                    return Err(CrustError::Parse { what: "latch reset value".to_string(), value: value.to_string() });
+                }
+            };
+            latches.push((next, init));
+        }
+
+        // 3. read outputs as ASCII
         let mut outputs = Vec::new();
         for _ in 0..o {
             let mut line = String::new();
             reader.read_line(&mut line)?;
             let val_str = line.trim(); // tim(): no whitspace or newlines(\n)
-            let val = val_str.parse::<u64>().expect("output not parseable");
-            outputs.push(to_signal(val));
+            let val = parse_number::<u64>(val_str, "output literal")?;
+            outputs.push(val);
         }
 
-        // 3. Read AIG-gates (deltas) and build AIG with own struct
+        // 4. Read AIG-gates (deltas) and build AIG with own struct
         // Explanation from the paper https://fmv.jku.at/aiger/FORMAT.aiger:
         // "The definition of an AND gate consists of three positive integers all
         // written on one line and separated by exactly one space character.  The
@@ -73,7 +104,28 @@ impl AigerReader {
         // lhs is not explictly stored because the lhs indices are all consecutive: (I+L+A)
         // input literals = 2, 4, 6, .... 2*i --> input variable indices = literal / 2 or 1, 2, 3, .... i
         // and literal = 2*(I+L)+2, 4*(I+L)+4, .... 2*(I+L+A)   --> and variable indices = i + l + 1  or lhs / 2, da lhs = 2 * (i + l) + 2 * n
-        let mut aig = AIG::new(); 
+        let mut aig = AIG::new();
+
+        // build i inputs and l latches first, so that every AndNode only references existing nodes
+        for k in 0..i {
+            aig.add_input(k + 1);
+        }
+        for (k, &(_, init)) in latches.iter().enumerate() {
+            aig.add_latch(i + k + 1, init);
+        }
+
+        // create_and returns an existing node (structural hashing) or a simplified signal (e.g. x AND x = x)
+        // instead of a new node, then there is no node with the lhs index. Every literal that is read
+        // afterwards is translated with this map, so it points to the signal that replaced the gate.
+        let mut replaced: HashMap<usize, Signal> = HashMap::new();
+        let translate = |literal: u64, replaced: &HashMap<usize, Signal>| -> Result<Signal> {
+            let signal = to_signal(literal)?;
+            Ok(match replaced.get(&signal.index()) {
+                Some(&replacement) => replacement ^ signal.is_complemented(),
+                None => signal,
+            })
+        };
+
         let base = 2 * (i + l) + 2; //start of the AND indices: (2 * (i + l) + 2)
         let base = base as u64;
         for n in 0..a {
@@ -81,44 +133,62 @@ impl AigerReader {
             let delta0 = read_leb(&mut reader)?; 
             let delta1 = read_leb(&mut reader)?;
             
-            // calculate the literals (not the indices because the deltas are calculated using the literals)
+            // calculate the literals (not the indices because the deltas are calculated using the literals).
+            // lhs > rhs0 >= rhs1, so delta0 must not be 0 and both deltas must not go below literal 0.
             let lhs = base + 2 * n as u64;
-            let rhs0 = lhs - delta0;
-            let rhs1 = rhs0 - delta1;
+            let invalid_delta = |delta: u64| CrustError::Parse {
+                what: format!("delta of AND gate {n} (lhs literal {lhs})"),
+                value: delta.to_string(),
+            };
+            let rhs0 = lhs.checked_sub(delta0).filter(|_| delta0 > 0).ok_or_else(|| invalid_delta(delta0))?;
+            let rhs1 = rhs0.checked_sub(delta1).ok_or_else(|| invalid_delta(delta1))?;
 
             // transform literals to signals and create the and-node
-            let rhs0_signal = to_signal(rhs0);
-            let rhs1_signal = to_signal(rhs1);
+            let rhs0_signal = translate(rhs0, &replaced)?;
+            let rhs1_signal = translate(rhs1, &replaced)?;
             let index = (lhs / 2) as usize;
-            aig.create_and(rhs0_signal, rhs1_signal, index);
+            let signal = aig.create_and(rhs0_signal, rhs1_signal, index);
+            if signal != Signal::new(index, false) {
+                replaced.insert(index, signal);
+            }
         }
 
-        // build i inputs
-        let mut inputs = Vec::new();
-        for k in 0..i {
-            let signal = Signal::new((k+1) as usize, false);
-            inputs.push(signal);
+        // the latch next states and the outputs are added last, because they usually point to AndNodes
+        for (k, &(next, _)) in latches.iter().enumerate() {
+            aig.set_latch_next(k, translate(next, &replaced)?);
+        }
+        for output in outputs {
+            aig.add_output(translate(output, &replaced)?);
         }
 
-        Ok(Self { aig, inputs, outputs })
+        Ok(Self { aig })
     }
 
     pub fn aig(&self) -> &AIG {
         &self.aig
     }
-    pub fn inputs(&self) -> &Vec<Signal> {
-        &self.inputs
+    pub fn inputs(&self) -> &[Signal] {
+        &self.aig.inputs
     }
-    pub fn outputs(&self) -> &Vec<Signal> {
-        &self.outputs
+    pub fn outputs(&self) -> &[Signal] {
+        &self.aig.outputs
     }
 }
 
+/// parse a single number of the AIGER file. `what` describes the value for the error message.
+fn parse_number<T: std::str::FromStr>(value: &str, what: &str) -> Result<T> {
+    value.parse().map_err(|_| CrustError::Parse {
+        what: what.to_string(),
+        value: value.to_string(),
+    })
+}
+
 /// To decode an aiger file, the little endian base decoder is used.
 /// Example:
 /// 0x85 = 10000101 -> lower 7 bits = 0000101 = 5 (highest bit set -> continue)
 /// 0x01 = 00000001 -> lower 7 bits = 0000001 = 1 (highest bit not set -> break)
-fn read_leb<R: Read>(reader: &mut R) -> io::Result<u64> {
+/// A number that does not fit into 64 bits is a parse error.
+fn read_leb<R: Read>(reader: &mut R) -> Result<u64> {
     let mut result = 0u64;
     let mut shift = 0u32; // stores how many bits to shift each 7 bit groupt
     loop {
@@ -131,7 +201,14 @@ fn read_leb<R: Read>(reader: &mut R) -> io::Result<u64> {
         // 3. The byte is casted to an unsigned 64 bit integer
         // 4. The 7 bit chunk is shifted to the correct position inside of the 64 bit integer
         // 5. The now calculated 64 bit integer is now combined with the result variable using the bitwise or.
-        result |= ((byte & 0x7F) as u64) << shift; 
+        let chunk = (byte & 0x7F) as u64;
+        if shift >= 64 || (chunk << shift) >> shift != chunk {
+            return Err(CrustError::Parse {
+                what: "delta (does not fit into 64 bits)".to_string(),
+                value: format!("byte {byte:#04x} at bit {shift}"),
+            });
+        }
+        result |= chunk << shift; 
         
         // the the highes bit is not set, then stop. Otherwise get the next 7 bits.
         if byte & 0x80 == 0 {
@@ -141,3 +218,48 @@ fn read_leb<R: Read>(reader: &mut R) -> io::Result<u64> {
     }
     Ok(result)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::AigerReader;
+    use crate::error::CrustError;
+
+    /// write the bytes into a temporary AIGER file and read it
+    fn read(name: &str, bytes: &[u8]) -> crate::Result<AigerReader> {
+        let path = std::env::temp_dir().join(format!("crust_read_aiger_{}_{name}.aig", std::process::id()));
+        std::fs::write(&path, bytes).unwrap();
+        let result = AigerReader::from_file(path.to_str().unwrap());
+        std::fs::remove_file(&path).unwrap();
+        result
+    }
+
+    #[test]
+    fn reads_a_single_and_gate() {
+        // x3 = x1 and x2: lhs 6, deltas 6 - 4 = 2 and 4 - 2 = 2
+        let reader = read("and", b"aig 3 2 0 1 1\n6\n\x02\x02").unwrap();
+        let node = &reader.aig.node_map[&3];
+        assert_eq!((node.left_signal.index(), node.right_signal.index()), (1, 2));
+        assert_eq!(reader.aig.outputs[0].index(), 3);
+    }
+
+    #[test]
+    fn rejects_a_literal_above_m() {
+        let result = read("literal", b"aig 3 2 0 1 1\n99\n\x02\x02");
+        assert!(matches!(result, Err(CrustError::Parse { value, .. }) if value == "99"));
+    }
+
+    #[test]
+    fn rejects_an_overlong_delta() {
+        // 10 continuation bytes: the 11th group of 7 bits would start at bit 70
+        let mut bytes = b"aig 3 2 0 1 1\n6\n".to_vec();
+        bytes.extend([0x80; 10]);
+        bytes.extend([0x01, 0x02]);
+        assert!(matches!(read("leb", &bytes), Err(CrustError::Parse { .. })));
+
+        // the 10th group starts at bit 63, only its lowest bit fits
+        let mut bytes = b"aig 3 2 0 1 1\n6\n".to_vec();
+        bytes.extend([0x80; 9]);
+        bytes.extend([0x02, 0x02]);
+        assert!(matches!(read("leb_bits", &bytes), Err(CrustError::Parse { .. })));
+    }
+}
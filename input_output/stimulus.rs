@@ -0,0 +1,95 @@
+use crate::aig_structure::aig::AIG;
+use crate::error::{CrustError, Result};
+use std::fs;
+
+/// Input vectors for the simulation of an AIG, one vector per cycle.
+/// init: initial values of the latches (only AIGER witnesses have them). None means that the reset values
+///       of the AIG are used.
+/// vectors: one value per input for every cycle, in AIGER order. None is an unknown value ('x').
+///
+/// Two file formats are accepted:
+/// - one vector per line, e.g. "0110" or "0 1 1 0". Empty lines and lines that start with '#' are skipped.
+/// - AIGER witnesses (https://fmv.jku.at/aiger/FORMAT): the status line ("1"), the property line
+///   (e.g. "b0"), the initial latch values and one line per cycle, terminated by ".".
+///
+/// In both formats the values are '0', '1' or 'x'.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stimulus {
+    pub init: Option<Vec<Option<bool>>>,
+    pub vectors: Vec<Vec<Option<bool>>>,
+}
+
+impl Stimulus {
+    pub fn from_file(path: &str, num_inputs: usize, num_latches: usize) -> Result<Self> {
+        Self::parse(&fs::read_to_string(path)?, num_inputs, num_latches)
+    }
+
+    /// Parse a stimulus for an AIG with num_inputs inputs and num_latches latches. Every vector must have
+    /// exactly one value per input (and the initial state of a witness one value per latch).
+    pub fn parse(text: &str, num_inputs: usize, num_latches: usize) -> Result<Self> {
+        let mut lines = text.lines().map(str::trim).skip_while(|line| line.is_empty() || line.starts_with('#')).peekable();
+        let mut init = None;
+
+        // a witness starts with the status and a line with the properties (b0, j0, ...)
+        let status = lines.next_if(|line| matches!(*line, "0" | "1" | "2"));
+        if let Some(status) = status {
+            if !lines.peek().is_some_and(|line| line.starts_with('b') || line.starts_with('j')) {
+                // not a witness, but a vector of an AIG with a single input
+                return Self::parse_vectors(std::iter::once(status).chain(lines), num_inputs, init);
+            }
+            if status != "1" {
+                return Err(CrustError::Parse {
+                    what: "witness status (only 1 = counterexample has a trace)".to_string(),
+                    value: status.to_string(),
+                });
+            }
+            lines.next();
+            // the initial state line is empty if there are no latches, so it is not skipped
+            let state = lines.next().unwrap_or_default();
+            init = Some(parse_values(state, num_latches, "initial latch state")?);
+        }
+        Self::parse_vectors(lines, num_inputs, init)
+    }
+
+    fn parse_vectors<'t>(lines: impl Iterator<Item = &'t str>, num_inputs: usize, init: Option<Vec<Option<bool>>>) -> Result<Self> {
+        let mut vectors = Vec::new();
+        for line in lines {
+            if line == "." {
+                break;
+            }
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            vectors.push(parse_values(line, num_inputs, "input vector")?);
+        }
+        Ok(Stimulus { init, vectors })
+    }
+
+    /// The state of the latches before the first cycle: the initial values of the witness, otherwise the
+    /// reset values of the AIG. Unknown witness values ('x') and uninitialized latches are None.
+    pub fn initial_state(&self, aig: &AIG) -> Vec<Option<bool>> {
+        aig.latches.iter().enumerate()
+            .map(|(i, latch)| self.init.as_ref().map_or(latch.init, |init| init[i]))
+            .collect()
+    }
+}
+
+/// the values of one line, whitespace is ignored
+fn parse_values(line: &str, count: usize, what: &str) -> Result<Vec<Option<bool>>> {
+    let values: Option<Vec<Option<bool>>> = line.chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| match c {
+            '0' => Some(Some(false)),
+            '1' => Some(Some(true)),
+            'x' | 'X' => Some(None),
+            _ => None,
+        })
+        .collect();
+    match values {
+        Some(values) if values.len() == count => Ok(values),
+        _ => Err(CrustError::Parse {
+            what: format!("{what} with {count} values"),
+            value: line.to_string(),
+        }),
+    }
+}
@@ -0,0 +1,89 @@
+use crate::algorithms::simulator::Trace;
+use crate::algorithms::ternary::Ternary;
+use std::io::{self, Write};
+
+/// Write a trace as table with one line per cycle. The values of the inputs, latches and outputs are
+/// written as strings of 0, 1 and x in AIGER order (like the vectors of a stimulus file), e.g.
+/// cycle  inputs  latches  outputs
+///     0  0110    01       1
+pub fn write_trace_table<W: Write, T: Copy + Into<Ternary>>(writer: &mut W, trace: &Trace<T>) -> io::Result<()> {
+    let width = |values: &[Vec<T>], header: &str| values.first().map_or(0, Vec::len).max(header.len());
+    let inputs_width = width(&trace.inputs, "inputs");
+    let latches_width = width(&trace.latches, "latches");
+    writeln!(writer, "cycle  {:<inputs_width$}  {:<latches_width$}  outputs", "inputs", "latches")?;
+    for cycle in 0..trace.num_cycles() {
+        writeln!(
+            writer,
+            "{:>5}  {:<inputs_width$}  {:<latches_width$}  {}",
+            cycle, bits(&trace.inputs[cycle]), bits(&trace.latches[cycle]), bits(&trace.outputs[cycle])
+        )?;
+    }
+    Ok(())
+}
+
+/// Write a trace as VCD waveform (IEEE 1364) that can be opened with GTKWave and other viewers.
+/// Every input, latch and output is a 1-bit wire (i0, i1, ..., l0, ..., o0, ...) in the scope "aig" with the
+/// value 0, 1 or x.
+/// Cycle k starts at time k, only the values that change are written. There is no $date section, so
+/// the same trace always gives the same file.
+pub fn write_vcd<W: Write, T: Copy + Into<Ternary>>(writer: &mut W, trace: &Trace<T>) -> io::Result<()> {
+    // all signals of a cycle in the order inputs, latches, outputs
+    let values = |cycle: usize| -> Vec<Ternary> {
+        [&trace.inputs[cycle], &trace.latches[cycle], &trace.outputs[cycle]].into_iter().flatten().map(|&value| value.into()).collect()
+    };
+    let counts = [
+        ("i", trace.inputs.first().map_or(0, Vec::len)),
+        ("l", trace.latches.first().map_or(0, Vec::len)),
+        ("o", trace.outputs.first().map_or(0, Vec::len)),
+    ];
+
+    writeln!(writer, "$version crust $end")?;
+    writeln!(writer, "$timescale 1ns $end")?;
+    writeln!(writer, "$scope module aig $end")?;
+    let mut id = 0;
+    for (prefix, count) in counts {
+        for i in 0..count {
+            writeln!(writer, "$var wire 1 {} {prefix}{i} $end", vcd_identifier(id))?;
+            id += 1;
+        }
+    }
+    writeln!(writer, "$upscope $end")?;
+    writeln!(writer, "$enddefinitions $end")?;
+
+    let mut previous: Option<Vec<Ternary>> = None;
+    for cycle in 0..trace.num_cycles() {
+        let current = values(cycle);
+        writeln!(writer, "#{cycle}")?;
+        if previous.is_none() {
+            writeln!(writer, "$dumpvars")?;
+        }
+        for (id, &value) in current.iter().enumerate() {
+            if previous.as_ref().is_none_or(|previous| previous[id] != value) {
+                writeln!(writer, "{}{}", value.symbol(), vcd_identifier(id))?;
+            }
+        }
+        if previous.is_none() {
+            writeln!(writer, "$end")?;
+        }
+        previous = Some(current);
+    }
+    // the end of the last cycle, so viewers show it with its full length
+    writeln!(writer, "#{}", trace.num_cycles())
+}
+
+fn bits<T: Copy + Into<Ternary>>(values: &[T]) -> String {
+    values.iter().map(|&value| value.into().symbol()).collect()
+}
+
+/// Short identifier of a VCD variable: base 94 with the printable characters '!' to '~'
+fn vcd_identifier(mut id: usize) -> String {
+    let mut identifier = String::new();
+    loop {
+        identifier.push((b'!' + (id % 94) as u8) as char);
+        id /= 94;
+        if id == 0 {
+            return identifier;
+        }
+        id -= 1;
+    }
+}
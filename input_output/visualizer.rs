@@ -1,41 +1,47 @@
 use crate::aig_structure::aig::AIG;
 use crate::aig_structure::signal::Signal;
+use crate::algorithms::dominators::{DominatorKind, DominatorTree};
+use crate::error::{CrustError, Result};
 use std::fs::File;
-use std::io::{Result, Write};
+use std::io::Write;
 use std::process::Command;
 
 
+/// Exports an AIG as DOT file and PNG image (with graphviz).
+/// aig: the graph
+/// base_path: directory of the exported files
+/// dominators: dominator trees that are drawn on top of the AIG. Every node gets an edge to its immediate
+///             dominator (red for the output dominators, blue for the input dominators).
 pub struct AigVisualizer<'a> {
     pub aig: &'a AIG,
-    pub base_path: String
+    pub base_path: String,
+    pub dominators: Vec<&'a DominatorTree>,
 }
 
 impl<'a> AigVisualizer<'a> {
-    pub fn new(aig: &'a AIG, base_path: &String) -> Self {
+    pub fn new(aig: &'a AIG, base_path: &str) -> Self {
         AigVisualizer { 
             aig,
-            base_path: base_path.to_string()
+            base_path: base_path.to_string(),
+            dominators: Vec::new(),
         }
     }
 
     pub fn export_png(&self, filename: &str, inputs: &[Signal], outputs: &[Signal]) -> Result<()> {
         
-        let _ = self.export_dot(filename, inputs, outputs);
+        self.export_dot(filename, inputs, outputs)?;
 
         let dotfile = format!("{}/{}.dot", self.base_path, filename);
         let pngfile = format!("{}/{}.png", self.base_path, filename);
 
         let status = Command::new("dot")
-        .args(&["-Tpng", &dotfile, "-o", &pngfile])
-        .status()
-        .expect("Failed to execute dot command");
-
-        if status.success() {
-            println!("Image succesfully created!");
-        } else {
-            eprintln!("dot-error: {}", status);
+        .args(["-Tpng", &dotfile, "-o", &pngfile])
+        .status()?;
+
+        if !status.success() {
+            return Err(CrustError::Graphviz(status.to_string()));
         }
-        
+
         Ok(())
 
     }
@@ -52,31 +58,74 @@ impl<'a> AigVisualizer<'a> {
             writeln!(
                 file,
                 "  x{} [label=\"x{}\", shape=box, style=filled, fillcolor=lightblue];",
-                input.index, input.index
+                input.index(), input.index()
+            )?;
+        }
+
+        // Latches: the next state signal goes into the latch
+        for latch in &self.aig.latches {
+            let init = match latch.init {
+                Some(true) => "1",
+                Some(false) => "0",
+                None => "x",
+            };
+            writeln!(
+                file,
+                "  x{} [label=\"x{} (init {})\", shape=box, style=filled, fillcolor=lightyellow];",
+                latch.index, latch.index, init
             )?;
+            let style = if latch.next.is_complemented() { "dashed" } else { "solid" };
+            writeln!(file, "  x{} -> x{} [style={}, constraint=false];", latch.next.index(), latch.index, style)?;
         }
 
-        // AND-Nodes
-        for (index, node) in &self.aig.node_map {
+        // AND-Nodes (sorted by id, so that the file is the same on every run)
+        let mut node_ids: Vec<&usize> = self.aig.node_map.keys().collect();
+        node_ids.sort_unstable();
+        for index in node_ids {
+            let node = &self.aig.node_map[index];
             writeln!(file, "  x{} [label=\"x{}\"];", index, index)?;
 
             for input in &[node.left_signal, node.right_signal] {
-                let style = if input.inverted { "dashed" } else { "solid" };
+                let style = if input.is_complemented() { "dashed" } else { "solid" };
                 writeln!(
                     file,
                     "  x{} -> x{} [style={}];",
-                    input.index, index, style
+                    input.index(), index, style
                 )?;
             }
         }
 
+        // Choices: dotted edge from every alternative to its representative
+        let mut reprs: Vec<&usize> = self.aig.choices.keys().collect();
+        reprs.sort_unstable();
+        for repr in reprs {
+            for choice in &self.aig.choices[repr] {
+                writeln!(
+                    file,
+                    "  x{} -> x{} [style=dotted, color=gray, constraint=false];",
+                    choice.index(), repr
+                )?;
+            }
+        }
+
+        // Dominator overlay: bold edge from every node to its immediate dominator
+        for tree in &self.dominators {
+            let color = match tree.kind {
+                DominatorKind::Outputs => "red",
+                DominatorKind::Inputs => "blue",
+            };
+            for (node, idom) in tree.edges() {
+                writeln!(file, "  x{} -> x{} [style=bold, color={}, constraint=false];", node, idom, color)?;
+            }
+        }
+
         // Outputs
         for (i, output) in outputs.iter().enumerate() {
-            let style = if output.inverted { "dashed" } else { "solid" };
-            let label = if output.inverted {
-                format!("f{} = ¬x{}", i, output.index)
+            let style = if output.is_complemented() { "dashed" } else { "solid" };
+            let label = if output.is_complemented() {
+                format!("f{} = ¬x{}", i, output.index())
             } else {
-                format!("f{} = x{}", i, output.index)
+                format!("f{} = x{}", i, output.index())
             };
 
             writeln!(
@@ -84,7 +133,7 @@ impl<'a> AigVisualizer<'a> {
                 "  f{} [label=\"{}\", shape=diamond, style=filled, fillcolor=lightgreen];",
                 i, label
             )?;
-            writeln!(file, "  x{} -> f{} [style={}];", output.index, i, style)?;
+            writeln!(file, "  x{} -> f{} [style={}];", output.index(), i, style)?;
         }
 
         writeln!(file, "}}")?;
@@ -0,0 +1,23 @@
+//! crust: cut enumeration for And-Inverter Graphs (AIGs).
+//!
+//! The crate is split into three parts:
+//! - `aig_structure`: the AIG itself (signals, AND nodes and the graph)
+//! - `algorithms`: algorithms that work on an AIG (cut enumeration and cut costs, truth tables and NPN
+//!   classes, MFFCs, dominators, binary and ternary simulation)
+//! - `input_output`: AIGER import, stimulus files, DOT/PNG export and cut and trace output
+//!
+//! The most important types are re-exported at the crate root, so a client only needs
+//! `use crust::{AigerReader, CutEnumerator};` to read a file and enumerate its cuts.
+
+pub mod aig_structure;
+pub mod algorithms;
+pub mod error;
+pub mod input_output;
+
+pub use aig_structure::aig::AIG;
+pub use aig_structure::and_node::AndNode;
+pub use aig_structure::signal::Signal;
+pub use algorithms::cut_enumerator::CutEnumerator;
+pub use error::{CrustError, Result};
+pub use input_output::read_aiger::AigerReader;
+pub use input_output::visualizer::AigVisualizer;
@@ -1,17 +1,25 @@
-#![allow(warnings)]
 use clap::Parser;
+use std::collections::BTreeMap;
 use std::fs::{self, File};
-use std::io::{self, Write};
+use std::io::{BufWriter, Write};
 
-mod aig_structure;
-mod algorithms;
-mod input_output;
-
-use aig_structure::aig::AIG;
-use crate::algorithms::cut_enumerator::CutEnumerator;
-use crate::input_output::read_aiger::AigerReader;
-use crate::input_output::visualizer::AigVisualizer;
+use crust::algorithms::cut::MAX_CUT_SIZE;
+use crust::algorithms::cut_enumerator::CutSortKey;
+use crust::algorithms::cut_limits::CutLimits;
+use crust::algorithms::dominators::{DominatorKind, DominatorTree};
+use crust::algorithms::exhaustive::{compare_truth_tables, output_truth_tables, parse_truth_tables, TruthTableFormat};
+use crust::algorithms::mffc::MffcCalculator;
+use crust::algorithms::npn::NpnStatistics;
+use crust::algorithms::simulator::{signature_to_hex, Simulator, Trace};
+use crust::algorithms::ternary::{Ternary, TernarySimulator};
+use crust::algorithms::truth_table::TruthTable;
+use crust::input_output::cut_writer::{write_cuts, CutFormat};
+use crust::input_output::stimulus::Stimulus;
+use crust::input_output::trace_writer::{write_trace_table, write_vcd};
+use crust::{AigVisualizer, AigerReader, CrustError, CutEnumerator, Result, AIG};
 use std::path::Path;
+use std::process::ExitCode;
+use std::time::Duration;
 
 
 /// AIG Processing Tool
@@ -30,25 +38,174 @@ struct Args {
     #[arg(short = 'c', long)]
     cut: Option<usize>,
 
+    /// Renumber the AIG into canonical AIGER order before everything else (the node ids of all outputs refer to the new numbering)
+    #[arg(long)]
+    normalize: bool,
+
     /// Enable graph visualization
     #[arg(short = 'v', long)]
     visualize: Option<String>,
 
+    /// Write the immediate dominators of every node (towards the outputs and from the inputs) to a file
+    #[arg(short = 'd', long)]
+    dominators: Option<String>,
+
+    /// Draw the dominator trees on top of the graph visualization (-v)
+    #[arg(long)]
+    dominator_overlay: bool,
+
     /// Maximum cut size (optional, default = 4)
     #[arg(short = 'k', long, default_value_t = 4)]
     max_cut_size: usize,
 
+    /// Maximum number of cuts per node, the trivial cut included (optional, default = no limit)
+    #[arg(short = 'l', long)]
+    cut_limit: Option<usize>,
+
+    /// Sort key that decides which cuts are kept if a cut limit is set: size, depth, area-flow or exact-area
+    #[arg(long, default_value = "size")]
+    cut_sort: CutSortKey,
+
+    /// Compute the truth table of every cut and write it next to the leaves
+    #[arg(short = 't', long)]
+    truth_tables: bool,
+
+    /// Compute the volume of every cut (internal nodes, nodes with external fanouts) for the json and csv format
+    #[arg(long)]
+    volume: bool,
+
+    /// Number of threads for the cut enumeration of all nodes (optional, default = 1)
+    #[arg(long, default_value_t = 1)]
+    threads: usize,
+
+    /// Stop the cut enumeration after this many seconds (optional, default = no limit)
+    #[arg(long)]
+    time_limit: Option<f64>,
+
+    /// Stop the cut enumeration when all nodes together have more cuts (optional, default = no limit)
+    #[arg(long)]
+    max_total_cuts: Option<usize>,
+
+    /// Stop the cut enumeration when a node has more minimal cuts (optional, default = no limit)
+    #[arg(long)]
+    max_node_cuts: Option<usize>,
+
+    /// Stop the cut enumeration when the cuts need more memory in MB (estimated, optional, default = no limit)
+    #[arg(long)]
+    max_memory: Option<usize>,
+
+    /// Write the maximum fanout-free cone (MFFC) of every AND node to a file
+    #[arg(short = 'm', long)]
+    mffc: Option<String>,
+
+    /// Simulate random patterns and write the signature of every node and output to a file
+    #[arg(long)]
+    simulate: Option<String>,
+
+    /// Number of 64-bit words per signature for --simulate (optional, default = 1)
+    #[arg(long, default_value_t = 1)]
+    sim_words: usize,
+
+    /// Seed of the random patterns for --simulate (optional, default = 1)
+    #[arg(long, default_value_t = 1)]
+    seed: u64,
+
+    /// Simulate the input vectors of a stimulus file (one vector per line or an AIGER witness) cycle by cycle
+    #[arg(long)]
+    stimulus: Option<String>,
+
+    /// Write the simulated values of --stimulus as table to this file (optional, default = print them)
+    #[arg(long)]
+    trace: Option<String>,
+
+    /// Write the simulated values of --stimulus as VCD waveform to this file
+    #[arg(long)]
+    vcd: Option<String>,
+
+    /// Simulate --stimulus with the values 0, 1 and x: unknown inputs and uninitialized latches stay unknown
+    #[arg(long)]
+    ternary: bool,
+
+    /// Find constant and stuck latches with a ternary simulation from the reset state
+    #[arg(long)]
+    latches: bool,
+
+    /// Maximum number of cycles of the latch analysis (optional, default = 1000)
+    #[arg(long, default_value_t = 1000)]
+    max_cycles: usize,
+
+    /// Print the complete truth table of every output (at most 16 inputs and latches)
+    #[arg(long)]
+    output_tt: bool,
+
+    /// Format of the output truth tables: hex or binary (optional, default = hex)
+    #[arg(long, default_value = "hex")]
+    tt_format: TruthTableFormat,
+
+    /// Compare the output truth tables with the truth tables in this file (one output per line)
+    #[arg(long)]
+    expected_tt: Option<String>,
+
+    /// Print how often every NPN class occurs in the cuts of all nodes
+    #[arg(short = 'n', long)]
+    npn: bool,
+
+    /// calculate sequential cuts (cuts across latches, every leaf has a latency) for all nodes
+    #[arg(short = 's', long)]
+    sequential: Option<String>,
+
+    /// Maximum number of latches between a leaf of a sequential cut and its node (optional, default = 1)
+    #[arg(long, default_value_t = 1)]
+    max_latency: usize,
+
+    /// Grow one reconvergence-driven cut with at most this many leaves for the node of -c instead of enumerating all cuts
+    #[arg(long)]
+    reconv_cut: Option<usize>,
+
+    /// Output format of the cuts (-e and -c): text, json or csv
+    #[arg(long, default_value = "text")]
+    format: CutFormat,
+
     /// Optional output path for single node cut result
     #[arg(short = 'o', long)]
     cut_output: Option<String>,
 
 }
 
-fn main() -> io::Result<()> {
+fn main() -> ExitCode {
     let args = Args::parse();
 
-    let mut reader = AigerReader::from_file(&args.read_aiger)?;
-    let aig = &mut reader.aig;
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: Args) -> Result<()> {
+    if args.max_cut_size == 0 || args.max_cut_size > MAX_CUT_SIZE {
+        return Err(CrustError::InvalidArgument(format!("the cut size has to be between 1 and {MAX_CUT_SIZE}")));
+    }
+    if args.cut_limit == Some(0) {
+        return Err(CrustError::InvalidArgument("the cut limit has to be at least 1 (the trivial cut is always kept)".to_string()));
+    }
+    let limits = cut_limits(&args)?;
+
+    let reader = AigerReader::from_file(&args.read_aiger)?;
+    let normalized;
+    let aig = if args.normalize {
+        normalized = reader.aig.normalize().0;
+        println!("AIG renumbered: {} AND nodes", normalized.node_map.len());
+        &normalized
+    } else {
+        &reader.aig
+    };
+
+    // output and input dominators, only computed if they are needed
+    let dominators = (args.dominators.is_some() || args.dominator_overlay)
+        .then(|| (DominatorTree::new(aig, DominatorKind::Outputs), DominatorTree::new(aig, DominatorKind::Inputs)));
 
     if let Some(path) = &args.visualize {
         let full_path = Path::new(path);
@@ -57,43 +214,310 @@ fn main() -> io::Result<()> {
          // 2. part: filename without .png
         let file_stem = full_path.file_stem().unwrap().to_str().unwrap();
 
-        let exporter = AigVisualizer::new(aig, &output_path.to_string());
-        exporter.export_png(&file_stem, &reader.inputs, &reader.outputs)?;
+        let mut exporter = AigVisualizer::new(aig, output_path);
+        if let (true, Some((output_dominators, input_dominators))) = (args.dominator_overlay, &dominators) {
+            exporter.dominators = vec![output_dominators, input_dominators];
+        }
+        exporter.export_png(file_stem, &aig.inputs, &aig.outputs)?;
         println!("Graph visualized at {output_path}/{file_stem}.png");
     }
 
     if let Some(path) = args.cut_enumerate {
         let mut cut_enumerator = CutEnumerator::new(aig);
-        cut_enumerator.enumerate_cuts(args.max_cut_size, &reader.inputs);
+        cut_enumerator.cut_limit = args.cut_limit;
+        cut_enumerator.sort_key = args.cut_sort;
+        cut_enumerator.compute_truth_tables = args.truth_tables;
+        cut_enumerator.threads = args.threads;
+        cut_enumerator.limits = limits.clone();
+        cut_enumerator.enumerate_cuts(args.max_cut_size, &aig.inputs);
+        print_status(&cut_enumerator);
 
         fs::create_dir_all(
             std::path::Path::new(&path).parent().unwrap_or_else(|| ".".as_ref())
         )?;
-        let mut file = File::create(&path)?;
-        writeln!(file, "{:?}", cut_enumerator.cuts)?;
+        let mut file = BufWriter::new(File::create(&path)?);
+        write_cuts(&mut file, args.format, &cut_enumerator.sorted_cuts(), args.volume.then_some(&cut_enumerator))?;
+        file.flush()?;
         println!("Cuts written to {path}");
+        print_skipped_nodes(&cut_enumerator);
         
     }
 
-    if let Some(target_node) = args.cut {
+    if let Some(path) = args.sequential {
         let mut cut_enumerator = CutEnumerator::new(aig);
+        cut_enumerator.cut_limit = args.cut_limit;
+        // without a cut limit, the latch cuts do not change anymore after max_latency + 2 iterations
+        let max_iterations = args.max_latency + 2;
+        let sequential_cuts = cut_enumerator.enumerate_sequential_cuts(args.max_cut_size, args.max_latency, max_iterations);
+
+        fs::create_dir_all(
+            std::path::Path::new(&path).parent().unwrap_or_else(|| ".".as_ref())
+        )?;
+        let mut file = File::create(&path)?;
+        let sorted: BTreeMap<_, _> = sequential_cuts.cuts.iter().filter(|(&node, _)| node != 0).collect();
+        writeln!(file, "{:?}", sorted)?;
+        println!("Sequential cuts written to {path}");
+        if !sequential_cuts.converged {
+            println!("Warning: the sequential cuts did not converge after {} iterations", sequential_cuts.iterations);
+        }
+    }
+
+    if let (Some(path), Some((output_dominators, input_dominators))) = (&args.dominators, &dominators) {
+        fs::create_dir_all(
+            std::path::Path::new(path).parent().unwrap_or_else(|| ".".as_ref())
+        )?;
+        let mut file = BufWriter::new(File::create(path)?);
+        let mut nodes: Vec<usize> = output_dominators.idom.keys().chain(input_dominators.idom.keys()).copied().collect();
+        nodes.sort_unstable();
+        nodes.dedup();
+        let format_idom = |idom: Option<usize>| idom.map_or("-".to_string(), |idom| idom.to_string());
+        for node in nodes.into_iter().filter(|&node| node != 0) {
+            writeln!(
+                file,
+                "node {node}: output dominator {}, input dominator {}",
+                format_idom(output_dominators.immediate_dominator(node)),
+                format_idom(input_dominators.immediate_dominator(node))
+            )?;
+        }
+        file.flush()?;
+        println!("Dominators written to {path}");
+    }
+
+    if let Some(path) = args.mffc {
+        let mut calculator = MffcCalculator::new(aig);
+        fs::create_dir_all(
+            std::path::Path::new(&path).parent().unwrap_or_else(|| ".".as_ref())
+        )?;
+        let mut file = BufWriter::new(File::create(&path)?);
+        for mffc in calculator.all_mffcs() {
+            writeln!(file, "{mffc}")?;
+        }
+        file.flush()?;
+        println!("MFFCs written to {path}");
+    }
+
+    if let Some(path) = &args.simulate {
+        if args.sim_words == 0 {
+            return Err(CrustError::InvalidArgument("--sim-words has to be at least 1".to_string()));
+        }
+        ensure_consistent(aig)?;
+        let mut simulator = Simulator::new(aig, args.sim_words);
+        simulator.randomize(args.seed);
+        simulator.run();
+
+        fs::create_dir_all(
+            std::path::Path::new(path).parent().unwrap_or_else(|| ".".as_ref())
+        )?;
+        let mut file = BufWriter::new(File::create(path)?);
+        let signatures: BTreeMap<_, _> = simulator.signatures.iter().filter(|(&node, _)| node != 0).collect();
+        for (node, words) in signatures {
+            writeln!(file, "node {node}: {}", signature_to_hex(words))?;
+        }
+        for (i, words) in simulator.output_values().iter().enumerate() {
+            writeln!(file, "output {i}: {}", signature_to_hex(words))?;
+        }
+        file.flush()?;
+        println!("Signatures of {} patterns written to {path}", 64 * args.sim_words);
+        println!("Equivalence candidates: {} classes", simulator.equivalence_candidates().len());
+    }
+
+    if let Some(path) = &args.stimulus {
+        ensure_consistent(aig)?;
+        let stimulus = Stimulus::from_file(path, aig.inputs.len(), aig.latches.len())?;
+        if args.ternary {
+            let ternary = |values: &[Option<bool>]| -> Vec<Ternary> { values.iter().map(|&value| Ternary::from(value)).collect() };
+            let vectors: Vec<Vec<Ternary>> = stimulus.vectors.iter().map(|vector| ternary(vector)).collect();
+            let trace = TernarySimulator::new(aig).simulate_cycles(&ternary(&stimulus.initial_state(aig)), &vectors);
+            write_trace(&trace, args.trace.as_deref(), args.vcd.as_deref())?;
+            let unknown_cycles: Vec<usize> = (0..trace.num_cycles())
+                .filter(|&cycle| trace.outputs[cycle].iter().any(|value| !value.is_known()))
+                .collect();
+            match unknown_cycles.first() {
+                Some(first) => println!("Unknown output values in {} of {} cycles (first in cycle {first})", unknown_cycles.len(), trace.num_cycles()),
+                None => println!("All output values are known"),
+            }
+        } else {
+            // the binary simulation has no unknown values, they are 0
+            let known = |values: &[Option<bool>]| -> Vec<bool> { values.iter().map(|value| value.unwrap_or(false)).collect() };
+            let vectors: Vec<Vec<bool>> = stimulus.vectors.iter().map(|vector| known(vector)).collect();
+            let trace = Simulator::new(aig, 1).simulate_cycles(&known(&stimulus.initial_state(aig)), &vectors)?;
+            write_trace(&trace, args.trace.as_deref(), args.vcd.as_deref())?;
+        }
+    }
+
+    if args.latches {
+        ensure_consistent(aig)?;
+        let analysis = TernarySimulator::new(aig).analyze_latches(args.max_cycles);
+        match analysis.loop_start {
+            Some(loop_start) => println!(
+                "Reset states repeat after {} cycles (loop from cycle {loop_start})", analysis.states.len()
+            ),
+            None => println!("No repeated reset state within {} cycles, only constant latches are detected", args.max_cycles),
+        }
+        for (latch, status) in aig.latches.iter().zip(&analysis.status) {
+            println!("latch {}: {status}", latch.index);
+        }
+    }
+
+    if args.output_tt || args.expected_tt.is_some() {
+        ensure_consistent(aig)?;
+        let truth_tables = output_truth_tables(aig)?;
+        if args.output_tt {
+            for (i, truth_table) in truth_tables.iter().enumerate() {
+                println!("output {i}: {}", args.tt_format.format(truth_table));
+            }
+        }
+
+        if let Some(path) = &args.expected_tt {
+            let num_vars = aig.inputs.len() + aig.latches.len();
+            let expected = parse_truth_tables(&fs::read_to_string(path)?, num_vars)?;
+            let mismatches = compare_truth_tables(&truth_tables, &expected);
+            if !mismatches.is_empty() {
+                let format_tt = |tt: Option<&TruthTable>| tt.map_or("-".to_string(), |tt| args.tt_format.format(tt));
+                for &i in &mismatches {
+                    println!("output {i}: expected {}, got {}", format_tt(expected.get(i)), format_tt(truth_tables.get(i)));
+                }
+                return Err(CrustError::Mismatch(format!("{} output truth table(s) differ from {path}", mismatches.len())));
+            }
+            println!("All {} output truth tables match {path}", truth_tables.len());
+        }
+    }
+
+    if args.npn {
+        // the streaming enumeration frees the cuts in topological order, it is not split into levels
+        if args.threads > 1 {
+            println!("Warning: -n runs on a single thread, --threads {} is ignored", args.threads);
+        }
+        let mut cut_enumerator = CutEnumerator::new(aig);
+        cut_enumerator.cut_limit = args.cut_limit;
+        cut_enumerator.sort_key = args.cut_sort;
+        cut_enumerator.compute_truth_tables = true;
+        cut_enumerator.limits = limits.clone();
+
+        // every cut is only needed once, so the cuts are not kept for the whole design
+        let mut statistics = NpnStatistics::new();
+        cut_enumerator.visit_cuts(args.max_cut_size, |node, cuts| statistics.add_node_cuts(node, cuts));
+        print_status(&cut_enumerator);
+        print!("{statistics}");
+    }
+
+    if let (Some(target_node), Some(max_leaves)) = (args.cut, args.reconv_cut) {
+        let Some(cut) = aig.reconvergence_cut(target_node, max_leaves) else {
+            return Err(CrustError::InvalidArgument(format!("node {target_node} not found in AIG or inputs")));
+        };
+        let text = format!("leaves: {:?}\ninternal nodes: {:?}", cut.leaves, cut.internal);
+        if let Some(output_path) = &args.cut_output {
+            fs::create_dir_all(
+                std::path::Path::new(&output_path).parent().unwrap_or_else(|| ".".as_ref())
+            )?;
+            let mut file = File::create(output_path)?;
+            writeln!(file, "{text}")?;
+            println!("Reconvergence-driven cut for node {target_node} written to {output_path}");
+        } else {
+            println!("Reconvergence-driven cut for node {target_node}:\n{text}");
+        }
+    } else if let Some(target_node) = args.cut {
+        let mut cut_enumerator = CutEnumerator::new(aig);
+        cut_enumerator.cut_limit = args.cut_limit;
+        cut_enumerator.sort_key = args.cut_sort;
+        cut_enumerator.compute_truth_tables = args.truth_tables;
+        cut_enumerator.limits = limits;
         let cuts_for_target_node = cut_enumerator.calculate_cuts_single_node(
             args.max_cut_size,
-            &reader.inputs,
+            &aig.inputs,
             target_node,
-        );
+        ).ok_or_else(|| CrustError::InvalidArgument(format!("node {target_node} is not part of the AIG")))?;
+        print_status(&cut_enumerator);
+        print_skipped_nodes(&cut_enumerator);
 
+        // the text format of a single node is the list of its cuts
+        let node_cuts = BTreeMap::from([(target_node, &cuts_for_target_node)]);
         if let Some(output_path) = &args.cut_output {
             fs::create_dir_all(
                 std::path::Path::new(&output_path).parent().unwrap_or_else(|| ".".as_ref())
             )?;
             let mut file = File::create(output_path)?;
-            writeln!(file, "{:?}", cuts_for_target_node)?;
+            if args.format == CutFormat::Text {
+                writeln!(file, "{:?}", cuts_for_target_node)?;
+            } else {
+                write_cuts(&mut file, args.format, &node_cuts, args.volume.then_some(&cut_enumerator))?;
+            }
             println!("Cuts for node {target_node} written to {output_path}");
-        } else {
+        } else if args.format == CutFormat::Text {
             println!("Cuts for node {target_node}: {:?}", cuts_for_target_node);
+        } else {
+            write_cuts(&mut std::io::stdout().lock(), args.format, &node_cuts, args.volume.then_some(&cut_enumerator))?;
         }
     }
 
     Ok(())
 }
+
+/// report how many AndNodes were not processed, because they are outside the transitive fanin
+fn print_skipped_nodes(cut_enumerator: &CutEnumerator) {
+    if cut_enumerator.skipped_nodes > 0 {
+        println!("Skipped {} nodes outside the transitive fanin", cut_enumerator.skipped_nodes);
+    }
+}
+
+/// the resource limits of the command line
+fn cut_limits(args: &Args) -> Result<CutLimits> {
+    let max_time = match args.time_limit {
+        Some(seconds) => Some(Duration::try_from_secs_f64(seconds)
+            .map_err(|_| CrustError::InvalidArgument(format!("invalid time limit {seconds}")))?),
+        None => None,
+    };
+    let max_memory = match args.max_memory {
+        Some(mb) => Some(mb.checked_mul(1024 * 1024)
+            .ok_or_else(|| CrustError::InvalidArgument(format!("memory limit {mb} MB is too large")))?),
+        None => None,
+    };
+    Ok(CutLimits {
+        max_time,
+        max_total_cuts: args.max_total_cuts,
+        max_cuts_per_node: args.max_node_cuts,
+        max_memory,
+    })
+}
+
+/// report if the cut enumeration was stopped by a limit, the written cuts are only a partial result then
+fn print_status(cut_enumerator: &CutEnumerator) {
+    if !cut_enumerator.status.is_complete() {
+        println!("Warning: cut enumeration stopped ({}), the result is incomplete", cut_enumerator.status);
+    }
+}
+
+/// write the trace of --stimulus as table (to --trace or the terminal) and as waveform (--vcd)
+fn write_trace<T: Copy + Into<Ternary>>(trace: &Trace<T>, table_path: Option<&str>, vcd_path: Option<&str>) -> Result<()> {
+    match table_path {
+        Some(path) => {
+            fs::create_dir_all(
+                std::path::Path::new(path).parent().unwrap_or_else(|| ".".as_ref())
+            )?;
+            let mut file = BufWriter::new(File::create(path)?);
+            write_trace_table(&mut file, trace)?;
+            file.flush()?;
+            println!("Trace of {} cycles written to {path}", trace.num_cycles());
+        }
+        None => write_trace_table(&mut std::io::stdout().lock(), trace)?,
+    }
+    if let Some(path) = vcd_path {
+        fs::create_dir_all(
+            std::path::Path::new(path).parent().unwrap_or_else(|| ".".as_ref())
+        )?;
+        let mut file = BufWriter::new(File::create(path)?);
+        write_vcd(&mut file, trace)?;
+        file.flush()?;
+        println!("Waveform written to {path}");
+    }
+    Ok(())
+}
+
+/// the simulation needs a consistent AIG, otherwise a fanin may not have a value
+fn ensure_consistent(aig: &AIG) -> Result<()> {
+    let report = aig.check();
+    if !report.is_ok() {
+        return Err(CrustError::InconsistentAig(report.to_string().trim_end().to_string()));
+    }
+    Ok(())
+}